@@ -0,0 +1,143 @@
+//! Throughput benchmarks of the kernel `jitterentropy_rng`
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rand_core::TryRngCore;
+use rand_jitter_kernel::{RandJitterKernel, RandJitterKernelPool};
+use std::hint::black_box;
+
+fn bench_next_u64(c: &mut Criterion) {
+    let mut rng = RandJitterKernel::new().unwrap();
+    c.bench_function("try_next_u64", |b| {
+        b.iter(|| black_box(rng.try_next_u64().unwrap()));
+    });
+}
+
+fn bench_u64_buffering(c: &mut Criterion) {
+    let mut rng = RandJitterKernel::new().unwrap();
+    let mut group = c.benchmark_group("u64");
+    // served from the internal buffer
+    group.bench_function("try_next_u64", |b| {
+        b.iter(|| black_box(rng.try_next_u64().unwrap()));
+    });
+    // one read() per value
+    group.bench_function("try_fill_bytes_8", |b| {
+        let mut bytes = [0u8; 8];
+        b.iter(|| rng.try_fill_bytes(black_box(&mut bytes)).unwrap());
+    });
+    // an in-memory reader leaves only the buffer itself, with no syscall per value a buffered
+    // value costs a few ns
+    #[cfg(feature = "test-util")]
+    group.bench_function("try_next_u64_reader", |b| {
+        struct Zeros;
+        impl rand_jitter_kernel::EntropyReader for Zeros {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                buf.fill(0);
+                Ok(buf.len())
+            }
+        }
+        let mut rng = RandJitterKernel::from_reader(Zeros);
+        b.iter(|| black_box(rng.try_next_u64().unwrap()));
+    });
+    group.finish();
+}
+
+fn bench_fill_bytes(c: &mut Criterion) {
+    let mut rng = RandJitterKernel::new().unwrap();
+    let mut group = c.benchmark_group("try_fill_bytes");
+    for size in [32usize, 128, 4096] {
+        group.throughput(Throughput::Bytes(u64::try_from(size).unwrap()));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let mut buf = vec![0u8; size];
+            b.iter(|| rng.try_fill_bytes(black_box(&mut buf)).unwrap());
+        });
+    }
+    group.finish();
+}
+
+fn bench_large_fill(c: &mut Criterion) {
+    const LEN: usize = 64 * 1024;
+    let mut rng = RandJitterKernel::new().unwrap();
+    let mut group = c.benchmark_group("fill_64k");
+    group.throughput(Throughput::Bytes(u64::try_from(LEN).unwrap()));
+    let mut buf = vec![0u8; LEN];
+    // one read() per chunk
+    group.bench_function("read", |b| {
+        b.iter(|| {
+            for chunk in buf.chunks_mut(rng.max_chunk_size()) {
+                std::io::Read::read_exact(&mut rng, black_box(chunk)).unwrap();
+            }
+        });
+    });
+    // batched into io_uring submissions with the io_uring feature
+    group.bench_function("try_fill_bytes", |b| {
+        b.iter(|| rng.try_fill_bytes(black_box(&mut buf)).unwrap());
+    });
+    group.finish();
+}
+
+fn bench_parallel_fill(c: &mut Criterion) {
+    const LEN: usize = 64 * 1024;
+    let mut group = c.benchmark_group("parallel_fill");
+    group.throughput(Throughput::Bytes(u64::try_from(LEN).unwrap()));
+    for instances in [1usize, 2, 4, 8] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(instances),
+            &instances,
+            |b, &instances| {
+                let mut buf = vec![0u8; LEN];
+                b.iter(|| RandJitterKernel::parallel_fill(black_box(&mut buf), instances).unwrap());
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_new(c: &mut Criterion) {
+    c.bench_function("new", |b| {
+        b.iter(|| black_box(RandJitterKernel::new().unwrap()));
+    });
+}
+
+fn bench_pool_acquire(c: &mut Criterion) {
+    let pool = RandJitterKernelPool::new().unwrap();
+    let mut group = c.benchmark_group("acquire");
+    group.bench_function("new", |b| {
+        b.iter(|| black_box(RandJitterKernel::new().unwrap()));
+    });
+    group.bench_function("pool", |b| {
+        b.iter(|| black_box(pool.acquire().unwrap()));
+    });
+    group.finish();
+}
+
+fn bench_batch_new(c: &mut Criterion) {
+    const COUNT: usize = 64;
+    let mut group = c.benchmark_group("construct_64");
+    group.bench_function("new", |b| {
+        b.iter(|| {
+            black_box(
+                (0..COUNT)
+                    .map(|_| RandJitterKernel::new())
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap(),
+            )
+        });
+    });
+    group.bench_function("batch_new", |b| {
+        b.iter(|| black_box(RandJitterKernel::batch_new(COUNT).unwrap()));
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_next_u64,
+    bench_u64_buffering,
+    bench_fill_bytes,
+    bench_large_fill,
+    bench_parallel_fill,
+    bench_new,
+    bench_pool_acquire,
+    bench_batch_new
+);
+criterion_main!(benches);
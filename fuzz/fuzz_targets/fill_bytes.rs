@@ -0,0 +1,80 @@
+//! Drives `try_fill_bytes` against a reader returning fuzzer-controlled `read()` results
+
+#![no_main]
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use rand_core::TryRngCore;
+use rand_jitter_kernel::{EntropyReader, RandJitterKernel};
+
+/// byte written by the reader, the destination starts zeroed
+const MARKER: u8 = 0xA5;
+
+#[derive(Debug, Arbitrary)]
+enum ReadResult {
+    /// reports `n` bytes, more than requested is a reader bug the rng has to reject
+    Data(u8),
+    /// end of file
+    Eof,
+    /// `EINTR`, retried by the rng
+    Interrupted,
+    /// any other OS error
+    Error(u8),
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    len: u16,
+    reads: Vec<ReadResult>,
+}
+
+struct FuzzReader {
+    reads: std::vec::IntoIter<ReadResult>,
+    /// number of bytes reported by reads not exceeding the request, which the rng accepts
+    reported: Arc<AtomicUsize>,
+}
+
+impl EntropyReader for FuzzReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // the rng never asks for more than one chunk
+        assert!(buf.len() <= 128);
+        match self.reads.next() {
+            Some(ReadResult::Data(n)) => {
+                let n = usize::from(n);
+                let written = n.min(buf.len());
+                buf[..written].fill(MARKER);
+                if n <= buf.len() {
+                    self.reported.fetch_add(n, Ordering::Relaxed);
+                }
+                Ok(n)
+            }
+            Some(ReadResult::Eof) | None => Ok(0),
+            Some(ReadResult::Interrupted) => Err(std::io::ErrorKind::Interrupted.into()),
+            Some(ReadResult::Error(code)) => {
+                Err(std::io::Error::from_raw_os_error(i32::from(code).max(1)))
+            }
+        }
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let reported = Arc::new(AtomicUsize::new(0));
+    let mut rng = RandJitterKernel::from_reader(FuzzReader {
+        reads: input.reads.into_iter(),
+        reported: Arc::clone(&reported),
+    });
+
+    let mut dst = vec![0u8; usize::from(input.len)];
+    let result = rng.try_fill_bytes(&mut dst);
+
+    let generated = usize::try_from(rng.bytes_generated()).unwrap();
+    assert!(generated <= dst.len());
+    assert_eq!(generated, reported.load(Ordering::Relaxed));
+    if result.is_ok() {
+        assert_eq!(generated, dst.len());
+        assert!(dst.iter().all(|&b| b == MARKER));
+    }
+});
@@ -0,0 +1,101 @@
+//! Pinning the calling thread to one CPU while it reads entropy
+
+use rand_core::TryRngCore;
+
+use crate::RandJitterKernel;
+
+/// restores the CPU affinity of the calling thread when dropped
+struct AffinityGuard {
+    previous: libc::cpu_set_t,
+}
+
+impl AffinityGuard {
+    /// pins the calling thread to `cpu` and remembers its previous affinity
+    fn pin(cpu: usize) -> Result<Self, std::io::Error> {
+        let previous = get_affinity()?;
+        // CPU_SET indexes a fixed-size mask, larger numbers are rejected like the kernel does
+        if cpu >= usize::try_from(libc::CPU_SETSIZE).unwrap_or(0) {
+            return Err(std::io::Error::from_raw_os_error(libc::EINVAL));
+        }
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+        set_affinity(&set)?;
+        Ok(AffinityGuard { previous })
+    }
+}
+
+impl Drop for AffinityGuard {
+    fn drop(&mut self) {
+        // the previous mask was accepted before, so restoring only fails if CPUs went offline
+        let _ = set_affinity(&self.previous);
+    }
+}
+
+fn get_affinity() -> Result<libc::cpu_set_t, std::io::Error> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, size_of::<libc::cpu_set_t>(), &raw mut set) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(set)
+}
+
+fn set_affinity(set: &libc::cpu_set_t) -> Result<(), std::io::Error> {
+    if unsafe { libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), set) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+impl RandJitterKernel {
+    /// fills `dst` like [`TryRngCore::try_fill_bytes`], with the calling thread pinned to `cpu`
+    /// via `sched_setaffinity()` for the duration of the reads
+    ///
+    /// The kernel collects jitter on the CPU that issues the `read()`, so this selects where the
+    /// entropy is gathered, e.g. on a CPU of the local NUMA node. Pinning costs two extra
+    /// syscalls per call and may migrate the thread, so it mainly pays off for background
+    /// fillers issuing large reads, which can pin their thread once instead. The previous
+    /// affinity is restored before returning.
+    ///
+    /// # Errors
+    /// Returns the OS error if `cpu` is out of range, offline or not allowed for this thread,
+    /// otherwise the same as [`TryRngCore::try_fill_bytes`].
+    pub fn try_fill_bytes_on_cpu(
+        &mut self,
+        dst: &mut [u8],
+        cpu: usize,
+    ) -> Result<(), std::io::Error> {
+        let _guard = AffinityGuard::pin(cpu)?;
+        self.try_fill_bytes(dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_affinity;
+    use crate::test_support::pipe_rng;
+
+    fn same_set(a: &libc::cpu_set_t, b: &libc::cpu_set_t) -> bool {
+        (0..usize::try_from(libc::CPU_SETSIZE).unwrap())
+            .all(|cpu| unsafe { libc::CPU_ISSET(cpu, a) == libc::CPU_ISSET(cpu, b) })
+    }
+
+    #[test]
+    fn test_fill_bytes_on_cpu() {
+        let data = [0x42u8; 16];
+        let (mut rng, _write_fd) = pipe_rng(&data);
+
+        let before = get_affinity().unwrap();
+        let cpu = (0..usize::try_from(libc::CPU_SETSIZE).unwrap())
+            .find(|&cpu| unsafe { libc::CPU_ISSET(cpu, &before) })
+            .unwrap();
+        let mut buf = [0u8; 16];
+        rng.try_fill_bytes_on_cpu(&mut buf, cpu).unwrap();
+        assert_eq!(buf, data);
+        assert!(same_set(&get_affinity().unwrap(), &before));
+
+        // nothing is read and the affinity stays untouched if pinning fails
+        let err = rng.try_fill_bytes_on_cpu(&mut buf, usize::MAX).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+        assert!(same_set(&get_affinity().unwrap(), &before));
+    }
+}
@@ -0,0 +1,108 @@
+//! Validated name of an `AF_ALG` rng algorithm
+
+use crate::RandJitterError;
+
+/// capacity of `salg_name` inside `sockaddr_alg`
+pub(crate) const SALG_NAME_LEN: usize = 64;
+
+/// longest name that still leaves room for the terminating NUL, which the kernel enforces
+pub(crate) const MAX_ALGORITHM_NAME_LEN: usize = SALG_NAME_LEN - 1;
+
+/// algorithm used by [`crate::RandJitterKernel::new`]
+pub(crate) const JITTERENTROPY_RNG: &str = "jitterentropy_rng";
+
+/// algorithm name that fits into `salg_name`, kept inline so options stay `Copy`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct AlgorithmName {
+    bytes: [u8; SALG_NAME_LEN],
+    len: usize,
+}
+
+impl AlgorithmName {
+    pub(crate) fn new(name: &str) -> Result<Self, RandJitterError> {
+        if name.len() > MAX_ALGORITHM_NAME_LEN {
+            return Err(RandJitterError::AlgorithmNameTooLong { len: name.len() });
+        }
+        if name.is_empty() || name.contains('\0') {
+            return Err(RandJitterError::InvalidAlgorithmName);
+        }
+
+        // the remaining bytes stay zero, so the copied name is always NUL-terminated
+        let mut bytes = [0u8; SALG_NAME_LEN];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        Ok(AlgorithmName {
+            bytes,
+            len: name.len(),
+        })
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        // constructed from a &str, so always valid UTF-8
+        std::str::from_utf8(self.as_bytes()).unwrap_or_default()
+    }
+}
+
+impl Default for AlgorithmName {
+    fn default() -> Self {
+        let mut bytes = [0u8; SALG_NAME_LEN];
+        bytes[..JITTERENTROPY_RNG.len()].copy_from_slice(JITTERENTROPY_RNG.as_bytes());
+        AlgorithmName {
+            bytes,
+            len: JITTERENTROPY_RNG.len(),
+        }
+    }
+}
+
+impl std::fmt::Debug for AlgorithmName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AlgorithmName, MAX_ALGORITHM_NAME_LEN, SALG_NAME_LEN};
+    use crate::RandJitterError;
+
+    #[test]
+    fn test_name_63_bytes() {
+        let name = "a".repeat(63);
+        let algorithm = AlgorithmName::new(&name).unwrap();
+        assert_eq!(algorithm.as_str(), name);
+        assert_eq!(algorithm.bytes[MAX_ALGORITHM_NAME_LEN], 0);
+    }
+
+    #[test]
+    fn test_name_64_bytes() {
+        let name = "a".repeat(SALG_NAME_LEN);
+        assert!(matches!(
+            AlgorithmName::new(&name),
+            Err(RandJitterError::AlgorithmNameTooLong { len: 64 })
+        ));
+    }
+
+    #[test]
+    fn test_name_over_length() {
+        let name = "a".repeat(200);
+        assert!(matches!(
+            AlgorithmName::new(&name),
+            Err(RandJitterError::AlgorithmNameTooLong { len: 200 })
+        ));
+    }
+
+    #[test]
+    fn test_name_empty_or_nul() {
+        assert!(matches!(
+            AlgorithmName::new(""),
+            Err(RandJitterError::InvalidAlgorithmName)
+        ));
+        assert!(matches!(
+            AlgorithmName::new("jitter\0entropy_rng"),
+            Err(RandJitterError::InvalidAlgorithmName)
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+//! Asynchronous reads driven by the tokio reactor
+
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use futures_core::Stream;
+use tokio::io::unix::AsyncFd;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::RandJitterKernel;
+
+/// rng instance in non-blocking mode, registered with the tokio reactor
+///
+/// Every read issues a single `read()` and returns at most one chunk of `max_chunk_size()`
+/// bytes, possibly fewer.
+/// Must be constructed from within a tokio runtime.
+#[derive(Debug)]
+pub struct AsyncRandJitterKernel {
+    inner: AsyncFd<RandJitterKernel>,
+}
+
+impl AsyncRandJitterKernel {
+    /// constructs new RNG instance and registers it with the reactor of the current runtime
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`], or the error of registering with the reactor.
+    pub fn new() -> Result<Self, std::io::Error> {
+        Self::from_rng(RandJitterKernel::builder().nonblocking(true).build()?)
+    }
+
+    /// switches an existing instance to non-blocking mode and registers it with the reactor
+    ///
+    /// # Errors
+    /// Returns an error if the descriptor cannot be switched to non-blocking mode or registered.
+    pub fn from_rng(mut rng: RandJitterKernel) -> Result<Self, std::io::Error> {
+        rng.set_nonblocking(true)?;
+        Ok(AsyncRandJitterKernel {
+            inner: AsyncFd::new(rng)?,
+        })
+    }
+
+    /// deregisters from the reactor and returns the instance, still in non-blocking mode
+    #[must_use]
+    pub fn into_inner(self) -> RandJitterKernel {
+        self.inner.into_inner()
+    }
+}
+
+impl AsyncRead for AsyncRandJitterKernel {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let this = self.get_mut();
+        loop {
+            let mut guard = ready!(this.inner.poll_read_ready_mut(cx))?;
+            let unfilled = buf.initialize_unfilled();
+            // a single read, so the bytes of a short read are returned instead of being dropped
+            // when the next read would block, and a WouldBlock error clears the readiness, so
+            // the next poll waits for the reactor
+            match guard.try_io(|inner| inner.get_mut().try_read_some(unfilled)) {
+                Ok(Ok(len)) => {
+                    buf.advance(len);
+                    return Poll::Ready(Ok(()));
+                }
+                Ok(Err(err)) => return Poll::Ready(Err(err)),
+                Err(_would_block) => {}
+            }
+        }
+    }
+}
+
+/// endless stream of `[u8; N]` chunks read from a non-blocking rng instance
+///
+/// Each item is a full chunk, collected from as many reads as needed. The stream never ends,
+/// an error is yielded as an item and the bytes collected so far are kept for the next chunk.
+#[derive(Debug)]
+pub struct EntropyStream<const N: usize> {
+    rng: AsyncRandJitterKernel,
+    chunk: [u8; N],
+    filled: usize,
+}
+
+impl<const N: usize> EntropyStream<N> {
+    /// constructs new RNG instance and registers it with the reactor of the current runtime
+    ///
+    /// # Errors
+    /// Same as [`AsyncRandJitterKernel::new`].
+    pub fn new() -> Result<Self, std::io::Error> {
+        Ok(Self::from(AsyncRandJitterKernel::new()?))
+    }
+
+    /// deregisters from the reactor and returns the instance, bytes of an unfinished chunk are
+    /// dropped
+    #[must_use]
+    pub fn into_inner(self) -> AsyncRandJitterKernel {
+        self.rng
+    }
+}
+
+impl<const N: usize> From<AsyncRandJitterKernel> for EntropyStream<N> {
+    fn from(rng: AsyncRandJitterKernel) -> Self {
+        EntropyStream {
+            rng,
+            chunk: [0u8; N],
+            filled: 0,
+        }
+    }
+}
+
+impl<const N: usize> Stream for EntropyStream<N> {
+    type Item = Result<[u8; N], std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while this.filled < N {
+            let mut guard = ready!(this.rng.inner.poll_read_ready_mut(cx))?;
+            let unfilled = &mut this.chunk[this.filled..];
+            // a single read, so bytes of a short read are kept when the next one would block
+            match guard.try_io(|inner| inner.get_mut().try_read_some(unfilled)) {
+                Ok(Ok(len)) => this.filled += len,
+                Ok(Err(err)) => return Poll::Ready(Some(Err(err))),
+                Err(_would_block) => {}
+            }
+        }
+
+        this.filled = 0;
+        Poll::Ready(Some(Ok(this.chunk)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{send_packet, seqpacket_pair};
+    use crate::{AsyncRandJitterKernel, EntropyStream, RandJitterKernel};
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_async_read() {
+        let mut rng = AsyncRandJitterKernel::new().unwrap();
+        let mut buffer = [0u8; 300];
+        rng.read_exact(&mut buffer).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_async_read_waits_for_data() {
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        let rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        let mut rng = AsyncRandJitterKernel::from_rng(rng).unwrap();
+
+        let sender = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            send_packet(peer_fd, &[7u8; 4]);
+        });
+
+        let mut buffer = [0u8; 4];
+        rng.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(buffer, [7u8; 4]);
+
+        sender.await.unwrap();
+        unsafe { libc::close(peer_fd) };
+    }
+
+    #[tokio::test]
+    async fn test_async_read_keeps_short_read() {
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        let data = [9u8; 4];
+        send_packet(peer_fd, &data);
+        let rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        let mut rng = AsyncRandJitterKernel::from_rng(rng).unwrap();
+
+        // nothing follows the 4 bytes, so a second read would block
+        let mut buffer = [0u8; 16];
+        assert_eq!(rng.read(&mut buffer).await.unwrap(), 4);
+        assert_eq!(buffer[..4], data);
+
+        unsafe { libc::close(peer_fd) };
+    }
+
+    #[tokio::test]
+    async fn test_entropy_stream() {
+        use futures_core::Stream;
+        use std::os::fd::FromRawFd;
+        use std::pin::Pin;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        let rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        let mut stream = EntropyStream::<16>::from(AsyncRandJitterKernel::from_rng(rng).unwrap());
+
+        let sender = tokio::spawn(async move {
+            // chunks span several packets, some arriving only after the stream polled
+            for packet in 0..6u8 {
+                send_packet(peer_fd, &[packet; 8]);
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        });
+
+        for chunk in 0..3u8 {
+            let item = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await;
+            let mut expected = [2 * chunk; 16];
+            expected[8..].fill(2 * chunk + 1);
+            assert_eq!(item.unwrap().unwrap(), expected);
+        }
+
+        sender.await.unwrap();
+        unsafe { libc::close(peer_fd) };
+    }
+}
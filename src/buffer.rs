@@ -0,0 +1,225 @@
+//! Internal read buffer serving small requests without a syscall each
+
+use std::sync::Once;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{MAX_RETURN_CHUNK_SIZE, RandJitterError, wipe};
+
+/// bumped in the child after every `fork()`, so a buffer can tell its bytes came from the parent
+static FORK_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+extern "C" fn bump_fork_generation() {
+    FORK_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// returns the current fork generation, registering the fork handler on first use
+fn fork_generation() -> u64 {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        // without the handler a child could hand out its parent's bytes, which is worse than
+        // aborting right away
+        let ret = unsafe { libc::pthread_atfork(None, None, Some(bump_fork_generation)) };
+        assert_eq!(ret, 0, "unable to register fork handler");
+    });
+    FORK_GENERATION.load(Ordering::Relaxed)
+}
+
+/// holds the unread rest of the last chunk read from the kernel
+///
+/// Consumed bytes are zeroed right away and the whole buffer is wiped on drop. Bytes read by
+/// another process, i.e. the parent before a `fork()`, are wiped instead of handed out.
+pub(crate) struct ReadBuffer {
+    data: Vec<u8>,
+    pos: usize,
+    len: usize,
+    /// fork generation of the process which filled the buffer
+    generation: u64,
+}
+
+/// empty buffer without an allocation
+impl Default for ReadBuffer {
+    fn default() -> Self {
+        ReadBuffer {
+            data: Vec::new(),
+            pos: 0,
+            len: 0,
+            generation: 0,
+        }
+    }
+}
+
+impl ReadBuffer {
+    pub(crate) fn new() -> Self {
+        Self::with_capacity(MAX_RETURN_CHUNK_SIZE)
+    }
+
+    /// buffer of `capacity` bytes, rounded up to a multiple of `chunk_size`
+    ///
+    /// Fails instead of aborting if the rounded capacity overflows or cannot be allocated, as it
+    /// may come from a config file.
+    pub(crate) fn with_chunks(capacity: usize, chunk_size: usize) -> Result<Self, RandJitterError> {
+        let rounded = capacity
+            .div_ceil(chunk_size)
+            .checked_mul(chunk_size)
+            .ok_or(RandJitterError::BufferCapacityOverflow { capacity })?;
+        let mut data = Vec::new();
+        data.try_reserve_exact(rounded)
+            .map_err(|_| RandJitterError::BufferAlloc { capacity: rounded })?;
+        data.resize(rounded, 0);
+        Ok(ReadBuffer {
+            data,
+            pos: 0,
+            len: 0,
+            generation: 0,
+        })
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        ReadBuffer {
+            data: vec![0u8; capacity],
+            pos: 0,
+            len: 0,
+            generation: 0,
+        }
+    }
+
+    /// number of bytes the buffer holds when full, 0 disables buffering
+    pub(crate) fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// number of buffered bytes not handed out yet
+    pub(crate) fn available(&self) -> usize {
+        self.len - self.pos
+    }
+
+    /// hands out up to `dst.len()` buffered bytes, returns the number of bytes copied
+    pub(crate) fn take(&mut self, dst: &mut [u8]) -> usize {
+        // a forked child must not hand out the bytes its parent hands out as well, comparing the
+        // generation avoids a getpid() syscall per request
+        if self.available() > 0 && self.generation != FORK_GENERATION.load(Ordering::Relaxed) {
+            self.clear();
+        }
+        let len = dst.len().min(self.available());
+        let taken = &mut self.data[self.pos..self.pos + len];
+        dst[..len].copy_from_slice(taken);
+        wipe(taken);
+        self.pos += len;
+        len
+    }
+
+    /// refills the empty buffer through `read`, which returns the number of bytes it wrote
+    pub(crate) fn refill<E>(
+        &mut self,
+        read: impl FnOnce(&mut [u8]) -> Result<usize, E>,
+    ) -> Result<usize, E> {
+        debug_assert_eq!(self.available(), 0);
+        self.clear();
+        let len = read(&mut self.data)?;
+        self.len = len.min(self.data.len());
+        self.generation = fork_generation();
+        Ok(self.len)
+    }
+
+    /// drops all buffered bytes
+    pub(crate) fn clear(&mut self) {
+        wipe(&mut self.data);
+        self.pos = 0;
+        self.len = 0;
+    }
+}
+
+/// never shows buffered entropy
+impl std::fmt::Debug for ReadBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReadBuffer")
+            .field("available", &self.available())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ReadBuffer {
+    fn drop(&mut self) {
+        wipe(&mut self.data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadBuffer;
+    use crate::RandJitterError;
+
+    #[test]
+    fn test_capacity_rounding() {
+        assert_eq!(ReadBuffer::with_chunks(0, 128).unwrap().capacity(), 0);
+        assert_eq!(ReadBuffer::with_chunks(1, 128).unwrap().capacity(), 128);
+        assert_eq!(ReadBuffer::with_chunks(128, 128).unwrap().capacity(), 128);
+        assert_eq!(ReadBuffer::with_chunks(129, 128).unwrap().capacity(), 256);
+    }
+
+    #[test]
+    fn test_capacity_too_large() {
+        assert!(matches!(
+            ReadBuffer::with_chunks(usize::MAX, 128),
+            Err(RandJitterError::BufferCapacityOverflow {
+                capacity: usize::MAX
+            })
+        ));
+        // rounds up to 2^63, which exceeds the maximum allocation of isize::MAX byte
+        assert!(matches!(
+            ReadBuffer::with_chunks(usize::MAX / 2, 128),
+            Err(RandJitterError::BufferAlloc { capacity }) if capacity == 1 << 63
+        ));
+    }
+
+    #[test]
+    fn test_wiped_after_fork() {
+        let mut buffer = ReadBuffer::with_chunks(16, 16).unwrap();
+        buffer
+            .refill(|data| {
+                data.fill(0x42);
+                Ok::<_, ()>(data.len())
+            })
+            .unwrap();
+        let mut dst = [0u8; 4];
+        assert_eq!(buffer.take(&mut dst), 4);
+        assert_eq!(dst, [0x42; 4]);
+
+        // as seen by a child forked after the refill
+        buffer.generation = buffer.generation.wrapping_add(1);
+        let mut dst = [0u8; 4];
+        assert_eq!(buffer.take(&mut dst), 0);
+        assert_eq!(dst, [0; 4]);
+        assert_eq!(buffer.available(), 0);
+        assert!(buffer.data.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_fork_bumps_generation() {
+        let mut buffer = ReadBuffer::with_chunks(16, 16).unwrap();
+        buffer
+            .refill(|data| {
+                data.fill(0x42);
+                Ok::<_, ()>(data.len())
+            })
+            .unwrap();
+
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            // only async-signal-safe work in the child of a multithreaded test process
+            let mut dst = [0u8; 4];
+            let taken = buffer.take(&mut dst);
+            unsafe { libc::_exit(i32::from(taken != 0 || dst != [0; 4])) };
+        }
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(pid, &raw mut status, 0) }, pid);
+        assert!(libc::WIFEXITED(status));
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+
+        // the parent keeps its bytes
+        let mut dst = [0u8; 4];
+        assert_eq!(buffer.take(&mut dst), 4);
+        assert_eq!(dst, [0x42; 4]);
+    }
+}
@@ -0,0 +1,262 @@
+//! Builder collecting the construction options of a [`RandJitterKernel`]
+
+use std::time::Duration;
+
+use crate::RandJitterKernel;
+use crate::algorithm::AlgorithmName;
+
+/// read timeout set by [`RandJitterKernelBuilder::test_mode`]
+const TEST_MODE_READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// type of the `AF_ALG` socket
+///
+/// The kernel only accepts [`SocketType::SeqPacket`] for `AF_ALG` sockets and rejects every other
+/// type with `ESOCKTNOSUPPORT`, reported as [`crate::RandJitterError::SocketCreate`]. The other
+/// types are offered for experiments with future kernels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum SocketType {
+    /// `SOCK_SEQPACKET`, the known-good default
+    #[default]
+    SeqPacket,
+    /// `SOCK_STREAM`
+    Stream,
+}
+
+impl SocketType {
+    /// returns the `SOCK_*` constant passed to `socket()`
+    #[must_use]
+    pub fn as_raw(self) -> libc::c_int {
+        match self {
+            SocketType::SeqPacket => libc::SOCK_SEQPACKET,
+            SocketType::Stream => libc::SOCK_STREAM,
+        }
+    }
+}
+
+/// options applied to an rng descriptor, kept by the instance so `reopen` can apply them again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[allow(clippy::struct_excessive_bools)] // independent flags, each set by its own builder method
+pub(crate) struct Options {
+    pub(crate) nonblocking: bool,
+    pub(crate) cloexec: bool,
+    pub(crate) read_timeout: Option<Duration>,
+    pub(crate) auto_reopen: bool,
+    pub(crate) algorithm: AlgorithmName,
+    pub(crate) buffer_capacity: usize,
+    pub(crate) socket_type: SocketType,
+    pub(crate) avoid_stdio_fds: bool,
+    pub(crate) warm_up: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            nonblocking: false,
+            cloexec: true,
+            read_timeout: None,
+            auto_reopen: false,
+            algorithm: AlgorithmName::default(),
+            buffer_capacity: crate::MAX_RETURN_CHUNK_SIZE,
+            socket_type: SocketType::SeqPacket,
+            avoid_stdio_fds: false,
+            warm_up: false,
+        }
+    }
+}
+
+/// builder for a [`RandJitterKernel`] with non-default options
+///
+/// [`RandJitterKernel::new`] is equivalent to `RandJitterKernel::builder().build()`.
+#[derive(Debug, Clone, Default)]
+pub struct RandJitterKernelBuilder {
+    options: Options,
+}
+
+impl RandJitterKernelBuilder {
+    /// creates a builder with default options
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// puts the rng descriptor into non-blocking mode, disabled by default
+    #[must_use]
+    pub fn nonblocking(mut self, nonblocking: bool) -> Self {
+        self.options.nonblocking = nonblocking;
+        self
+    }
+
+    /// opens the descriptors with the close-on-exec flag, enabled by default
+    #[must_use]
+    pub fn cloexec(mut self, cloexec: bool) -> Self {
+        self.options.cloexec = cloexec;
+        self
+    }
+
+    /// sets a timeout for reads from the rng descriptor, `None` (the default) blocks indefinitely
+    #[must_use]
+    pub fn read_timeout(mut self, read_timeout: Option<Duration>) -> Self {
+        self.options.read_timeout = read_timeout;
+        self
+    }
+
+    /// reopens the descriptor once if a read fails with `EBADF` or hits end of file, disabled by
+    /// default
+    #[must_use]
+    pub fn auto_reopen(mut self, auto_reopen: bool) -> Self {
+        self.options.auto_reopen = auto_reopen;
+        self
+    }
+
+    /// sets the size of the internal buffer serving small requests like `try_next_u64`, rounded
+    /// up to a multiple of the chunk size, one chunk by default
+    ///
+    /// A larger buffer needs fewer reads for many small requests, but holds more memory and
+    /// makes the refilling request wait for several chunks. 0 disables buffering, every small
+    /// request then issues its own read. A child created by `fork()` wipes the bytes buffered
+    /// by its parent and reads its own, so both never hand out the same bytes.
+    #[must_use]
+    pub fn buffer_capacity(mut self, bytes: usize) -> Self {
+        self.options.buffer_capacity = bytes;
+        self
+    }
+
+    /// sets the type of the `AF_ALG` socket, [`SocketType::SeqPacket`] by default, see
+    /// [`SocketType`] for the types the kernel accepts
+    #[must_use]
+    pub fn socket_type(mut self, socket_type: SocketType) -> Self {
+        self.options.socket_type = socket_type;
+        self
+    }
+
+    /// moves the rng descriptor above stderr if the kernel hands out 0, 1 or 2, disabled by
+    /// default
+    ///
+    /// If a process runs with stdio closed, the kernel reuses these numbers for new descriptors.
+    /// Code that later writes to stdout or stderr would then write into the rng descriptor. With
+    /// this option, such a descriptor is duplicated to the lowest free number of at least 3 and
+    /// the original is closed. This also applies to `try_clone`.
+    #[must_use]
+    pub fn avoid_stdio_fds(mut self, avoid_stdio_fds: bool) -> Self {
+        self.options.avoid_stdio_fds = avoid_stdio_fds;
+        self
+    }
+
+    /// discards 4 KiB via [`RandJitterKernel::warm_up`] before returning the instance, disabled
+    /// by default
+    #[must_use]
+    pub fn warm_up(mut self, warm_up: bool) -> Self {
+        self.options.warm_up = warm_up;
+        self
+    }
+
+    /// configures the instance for minimal latency in tests and CI, disabled by default, **not
+    /// meant for production**
+    ///
+    /// Sets a read timeout of 1 s and disables the warm-up, so slow VMs without much hardware
+    /// jitter fail fast instead of hanging. A read exceeding the timeout fails with
+    /// `std::io::ErrorKind::TimedOut`, which a test can treat as a reason to skip. Options set
+    /// after this call override it.
+    #[must_use]
+    pub fn test_mode(mut self, test_mode: bool) -> Self {
+        if test_mode {
+            self.options.read_timeout = Some(TEST_MODE_READ_TIMEOUT);
+            self.options.warm_up = false;
+        }
+        self
+    }
+
+    /// constructs new RNG instance with the configured options
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`]. Additionally, applying an option may fail with
+    /// [`crate::RandJitterError::SetOption`], and a buffer capacity which overflows or cannot be
+    /// allocated fails with [`crate::RandJitterError::BufferCapacityOverflow`] or
+    /// [`crate::RandJitterError::BufferAlloc`].
+    pub fn build(&self) -> Result<RandJitterKernel, std::io::Error> {
+        Ok(RandJitterKernel::open(&self.options)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RandJitterKernel;
+    use rand_core::TryRngCore;
+    use std::time::Duration;
+
+    #[test]
+    fn test_builder_defaults() {
+        let mut rng = RandJitterKernel::builder().build().unwrap();
+        let flags = unsafe { libc::fcntl(rng.rng_fd, libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+        assert!(rng.try_next_u64().is_ok());
+    }
+
+    #[test]
+    fn test_builder_options() {
+        let mut rng = RandJitterKernel::builder()
+            .nonblocking(true)
+            .cloexec(false)
+            .read_timeout(Some(Duration::from_secs(5)))
+            .auto_reopen(true)
+            .warm_up(true)
+            .build()
+            .unwrap();
+
+        let fd_flags = unsafe { libc::fcntl(rng.rng_fd, libc::F_GETFD) };
+        assert_eq!(fd_flags & libc::FD_CLOEXEC, 0);
+        let status_flags = unsafe { libc::fcntl(rng.rng_fd, libc::F_GETFL) };
+        assert_ne!(status_flags & libc::O_NONBLOCK, 0);
+        assert!(rng.options.auto_reopen);
+        assert!(rng.try_next_u64().is_ok());
+    }
+
+    #[test]
+    fn test_builder_buffer_too_large() {
+        let err = RandJitterKernel::builder()
+            .buffer_capacity(usize::MAX)
+            .build()
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        let err = RandJitterKernel::builder()
+            .buffer_capacity(usize::MAX / 2)
+            .build()
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+    }
+
+    #[test]
+    fn test_builder_test_mode() {
+        let builder = RandJitterKernel::builder().warm_up(true).test_mode(true);
+        assert_eq!(builder.options.read_timeout, Some(Duration::from_secs(1)));
+        assert!(!builder.options.warm_up);
+
+        let builder = builder.read_timeout(Some(Duration::from_millis(100)));
+        assert_eq!(
+            builder.options.read_timeout,
+            Some(Duration::from_millis(100))
+        );
+
+        let builder = RandJitterKernel::builder().test_mode(false);
+        assert_eq!(builder.options, crate::builder::Options::default());
+    }
+
+    #[test]
+    fn test_builder_socket_type() {
+        use crate::SocketType;
+
+        let mut rng = RandJitterKernel::builder()
+            .socket_type(SocketType::default())
+            .build()
+            .unwrap();
+        assert_eq!(rng.options.socket_type, SocketType::SeqPacket);
+        let mut buf = [0u8; 64];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert!(buf.iter().any(|&b| b != 0));
+
+        assert_eq!(SocketType::SeqPacket.as_raw(), libc::SOCK_SEQPACKET);
+        assert_eq!(SocketType::Stream.as_raw(), libc::SOCK_STREAM);
+    }
+}
@@ -0,0 +1,103 @@
+//! Serializable description of how to construct a [`RandJitterKernel`]
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::RandJitterKernel;
+use crate::algorithm::{AlgorithmName, JITTERENTROPY_RNG};
+use crate::builder::Options;
+
+/// configuration of an rng instance, e.g. loaded from a config file
+///
+/// Only the configuration is serializable, an open [`RandJitterKernel`] is not. Missing fields
+/// take their default values, which match [`RandJitterKernel::new`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RandJitterConfig {
+    /// name of the `AF_ALG` rng algorithm, `jitterentropy_rng` by default
+    pub algorithm: String,
+    /// size of the internal buffer, see [`crate::RandJitterKernelBuilder::buffer_capacity`]
+    pub buffer_capacity: usize,
+    /// timeout for reads, `None` (the default) blocks indefinitely
+    pub read_timeout: Option<Duration>,
+    /// puts the descriptor into non-blocking mode, disabled by default
+    pub nonblocking: bool,
+    /// opens the descriptors with the close-on-exec flag, enabled by default
+    pub cloexec: bool,
+    /// reopens the descriptor once if a read fails with `EBADF` or hits end of file, disabled by
+    /// default
+    pub auto_reopen: bool,
+}
+
+impl Default for RandJitterConfig {
+    fn default() -> Self {
+        let options = Options::default();
+        RandJitterConfig {
+            algorithm: JITTERENTROPY_RNG.to_string(),
+            buffer_capacity: options.buffer_capacity,
+            read_timeout: options.read_timeout,
+            nonblocking: options.nonblocking,
+            cloexec: options.cloexec,
+            auto_reopen: options.auto_reopen,
+        }
+    }
+}
+
+impl RandJitterConfig {
+    /// constructs new RNG instance with this configuration
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::with_algorithm`] and [`crate::RandJitterKernelBuilder::build`].
+    pub fn build(&self) -> Result<RandJitterKernel, std::io::Error> {
+        let options = Options {
+            nonblocking: self.nonblocking,
+            cloexec: self.cloexec,
+            read_timeout: self.read_timeout,
+            auto_reopen: self.auto_reopen,
+            algorithm: AlgorithmName::new(&self.algorithm)?,
+            buffer_capacity: self.buffer_capacity,
+            ..Options::default()
+        };
+        Ok(RandJitterKernel::open(&options)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RandJitterConfig;
+    use std::time::Duration;
+
+    #[test]
+    fn test_config_roundtrip() {
+        let config = RandJitterConfig {
+            read_timeout: Some(Duration::from_millis(1500)),
+            buffer_capacity: 512,
+            ..RandJitterConfig::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<RandJitterConfig>(&json).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config: RandJitterConfig = serde_json::from_str(r#"{"buffer_capacity": 0}"#).unwrap();
+        assert_eq!(config.algorithm, "jitterentropy_rng");
+        assert_eq!(config.buffer_capacity, 0);
+        assert!(config.cloexec);
+        assert_eq!(config.read_timeout, None);
+    }
+
+    #[test]
+    fn test_config_invalid_algorithm() {
+        let config = RandJitterConfig {
+            algorithm: String::new(),
+            ..RandJitterConfig::default()
+        };
+        let err = config.build().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
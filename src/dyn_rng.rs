@@ -0,0 +1,63 @@
+//! Object-safe counterpart of `TryRngCore` for storing rngs as trait objects
+
+use rand_core::TryRngCore;
+
+/// dyn-compatible subset of [`TryRngCore`], implemented for every `TryRngCore`
+///
+/// `TryRngCore` itself cannot be made into a trait object, as `unwrap_mut` lacks a
+/// `Self: Sized` bound. A `Box<dyn DynTryRng<Error = E> + Send>` implements `TryRngCore` again,
+/// so it can be passed wherever an rng is expected.
+pub trait DynTryRng {
+    /// error type of the wrapped rng
+    type Error;
+
+    /// returns the next random `u32`
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u32`] of the wrapped rng.
+    fn dyn_try_next_u32(&mut self) -> Result<u32, Self::Error>;
+
+    /// returns the next random `u64`
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u64`] of the wrapped rng.
+    fn dyn_try_next_u64(&mut self) -> Result<u64, Self::Error>;
+
+    /// fills `dst` entirely with random bytes
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`] of the wrapped rng.
+    fn dyn_try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+impl<R: TryRngCore> DynTryRng for R {
+    type Error = R::Error;
+
+    fn dyn_try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        self.try_next_u32()
+    }
+
+    fn dyn_try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        self.try_next_u64()
+    }
+
+    fn dyn_try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        self.try_fill_bytes(dst)
+    }
+}
+
+impl<E: std::fmt::Debug + std::fmt::Display> TryRngCore for Box<dyn DynTryRng<Error = E> + Send> {
+    type Error = E;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        (**self).dyn_try_next_u32()
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        (**self).dyn_try_next_u64()
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        (**self).dyn_try_fill_bytes(dst)
+    }
+}
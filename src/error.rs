@@ -0,0 +1,240 @@
+//! Error type describing why talking to the `jitterentropy_rng` inside the kernel failed
+
+use std::fmt;
+
+/// reason why an operation on [`crate::RandJitterKernel`] failed
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RandJitterError {
+    /// the `AF_ALG` socket could not be created
+    SocketCreate(std::io::Error),
+    /// creating the `AF_ALG` socket failed with `EPERM` or `EACCES`, usually because a seccomp
+    /// filter or an LSM policy blocks `AF_ALG`
+    PermissionDenied(std::io::Error),
+    /// the `AF_ALG` socket could not be bound to `jitterentropy_rng`
+    Bind(std::io::Error),
+    /// binding failed with `ENOENT` because `jitterentropy_rng` is not registered in the kernel
+    NotRegistered(std::io::Error),
+    /// no rng instance could be accepted from the bound `AF_ALG` socket
+    Accept(std::io::Error),
+    /// the process (`EMFILE`) or the system (`ENFILE`) ran out of file descriptors
+    FdLimit(std::io::Error),
+    /// the limit set by [`crate::RandJitterKernel::set_max_instances`] is reached
+    TooManyInstances {
+        /// maximum number of open instances
+        max: usize,
+    },
+    /// an option could not be applied to the rng file descriptor
+    SetOption(std::io::Error),
+    /// more bytes were requested in a single read than the kernel returns per call
+    ChunkTooLarge {
+        /// number of bytes requested
+        requested: usize,
+        /// maximum number of bytes returned per read, see
+        /// [`crate::RandJitterKernel::max_chunk_size`]
+        max: usize,
+    },
+    /// reading stopped at end of file before all requested bytes were returned
+    ShortRead {
+        /// number of bytes returned by the kernel
+        got: usize,
+        /// number of bytes requested
+        expected: usize,
+    },
+    /// a read returned 0 bytes for a non-empty request, so the source is exhausted or the socket
+    /// was shut down
+    SourceClosed,
+    /// a read reported more bytes than requested, which `read()` never does
+    OverRead {
+        /// number of bytes reported
+        got: usize,
+        /// number of bytes requested
+        requested: usize,
+    },
+    /// reading from the rng instance failed
+    Read(std::io::Error),
+    /// writing the random bytes to the destination failed
+    Write(std::io::Error),
+    /// closing the rng descriptor failed, it is released nevertheless
+    Close(std::io::Error),
+    /// the rng file descriptor is closed or was never opened
+    InvalidFd,
+    /// no data arrived within the configured read timeout
+    TimedOut,
+    /// the deadline passed before the buffer was filled
+    DeadlineExceeded {
+        /// number of bytes written to the start of the buffer
+        filled: usize,
+        /// number of bytes requested
+        expected: usize,
+    },
+    /// [`crate::RandJitterKernel::self_test`] read an all-zero sample
+    SelfTestFailed,
+    /// a random value was requested from an empty range
+    EmptyRange,
+    /// `AF_ALG` is not available on the target operating system
+    Unsupported,
+    /// the running kernel predates `jitterentropy_rng`, see [`crate::check_kernel_support`]
+    KernelTooOld {
+        /// release string of the running kernel
+        release: String,
+    },
+    /// the algorithm name is empty or contains a NUL byte
+    InvalidAlgorithmName,
+    /// the algorithm name does not fit into `salg_name` together with its terminating NUL
+    AlgorithmNameTooLong {
+        /// length of the rejected name in bytes
+        len: usize,
+    },
+    /// the buffer capacity overflows when rounded up to a multiple of the chunk size
+    BufferCapacityOverflow {
+        /// requested capacity in bytes
+        capacity: usize,
+    },
+    /// the internal buffer could not be allocated
+    BufferAlloc {
+        /// capacity in bytes, rounded up to a multiple of the chunk size
+        capacity: usize,
+    },
+}
+
+impl RandJitterError {
+    /// returns the OS error code of the failed syscall, if there is one
+    #[must_use]
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.os_error().and_then(std::io::Error::raw_os_error)
+    }
+
+    fn os_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Self::SocketCreate(err)
+            | Self::PermissionDenied(err)
+            | Self::Bind(err)
+            | Self::NotRegistered(err)
+            | Self::Accept(err)
+            | Self::FdLimit(err)
+            | Self::SetOption(err)
+            | Self::Read(err)
+            | Self::Write(err)
+            | Self::Close(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RandJitterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SocketCreate(_) => {
+                write!(f, "unable to create AF_ALG socket for jitterentropy_rng")
+            }
+            Self::PermissionDenied(_) => write!(
+                f,
+                "unable to create AF_ALG socket: permission denied, AF_ALG may be blocked by a \
+                 seccomp filter or LSM policy, consider falling back to another rng \
+                 (e.g. the `fallback` feature)"
+            ),
+            Self::Bind(_) => write!(f, "unable to bind AF_ALG socket"),
+            Self::NotRegistered(_) => write!(
+                f,
+                "unable to bind AF_ALG socket: jitterentropy_rng is not registered in the kernel, \
+                 try loading it with `modprobe jitterentropy_rng`"
+            ),
+            Self::Accept(_) => write!(f, "unable to get rng_fd from kernel"),
+            Self::FdLimit(_) => write!(
+                f,
+                "unable to open AF_ALG socket: the file descriptor limit is reached, \
+                 close unused instances or raise RLIMIT_NOFILE (e.g. `ulimit -n`)"
+            ),
+            Self::TooManyInstances { max } => write!(
+                f,
+                "unable to open another rng instance: {max} instances are already open"
+            ),
+            Self::SetOption(_) => write!(f, "unable to set option on rng_fd"),
+            Self::ChunkTooLarge { requested, max } => write!(
+                f,
+                "Cannot return more than the maximum chunk size of {max} byte in a single call. Requested: {requested} byte"
+            ),
+            Self::ShortRead { got, expected } => write!(
+                f,
+                "Cannot get entropy from jitterentropy_rng in kernel: got {got} of {expected} byte"
+            ),
+            Self::OverRead { got, requested } => write!(
+                f,
+                "Cannot get entropy from jitterentropy_rng in kernel: read reported {got} of {requested} requested byte"
+            ),
+            Self::SourceClosed => write!(
+                f,
+                "Cannot get entropy from jitterentropy_rng in kernel: the source is closed"
+            ),
+            Self::Read(_) => write!(f, "Cannot get entropy from jitterentropy_rng in kernel"),
+            Self::Write(_) => write!(f, "unable to write entropy from jitterentropy_rng"),
+            Self::Close(_) => write!(f, "unable to close rng_fd"),
+            Self::InvalidFd => write!(
+                f,
+                "Cannot get entropy from jitterentropy_rng in kernel with invalid fd"
+            ),
+            Self::TimedOut => write!(
+                f,
+                "Cannot get entropy from jitterentropy_rng in kernel within the read timeout"
+            ),
+            Self::DeadlineExceeded { filled, expected } => write!(
+                f,
+                "Cannot get entropy from jitterentropy_rng in kernel before the deadline: got {filled} of {expected} byte"
+            ),
+            Self::SelfTestFailed => write!(
+                f,
+                "jitterentropy_rng in kernel failed the self test: sample is all zero"
+            ),
+            Self::EmptyRange => write!(f, "cannot sample from an empty range"),
+            Self::Unsupported => write!(f, "AF_ALG is only supported on Linux"),
+            Self::KernelTooOld { release } => write!(
+                f,
+                "kernel {release} is too old for jitterentropy_rng, which requires Linux 4.2 or newer"
+            ),
+            Self::InvalidAlgorithmName => {
+                write!(f, "algorithm name must be non-empty and without NUL bytes")
+            }
+            Self::AlgorithmNameTooLong { len } => write!(
+                f,
+                "algorithm name is {len} byte long, at most 63 byte fit into salg_name"
+            ),
+            Self::BufferCapacityOverflow { capacity } => write!(
+                f,
+                "buffer capacity of {capacity} byte overflows when rounded up to whole chunks"
+            ),
+            Self::BufferAlloc { capacity } => {
+                write!(f, "unable to allocate a buffer of {capacity} byte")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RandJitterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.os_error()
+            .map(|err| err as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<RandJitterError> for std::io::Error {
+    fn from(err: RandJitterError) -> Self {
+        let kind = match &err {
+            RandJitterError::TimedOut | RandJitterError::DeadlineExceeded { .. } => {
+                std::io::ErrorKind::TimedOut
+            }
+            RandJitterError::Unsupported | RandJitterError::KernelTooOld { .. } => {
+                std::io::ErrorKind::Unsupported
+            }
+            RandJitterError::EmptyRange
+            | RandJitterError::InvalidAlgorithmName
+            | RandJitterError::AlgorithmNameTooLong { .. }
+            | RandJitterError::BufferCapacityOverflow { .. } => std::io::ErrorKind::InvalidInput,
+            RandJitterError::BufferAlloc { .. } => std::io::ErrorKind::OutOfMemory,
+            _ => err
+                .os_error()
+                .map_or(std::io::ErrorKind::Other, std::io::Error::kind),
+        };
+        std::io::Error::new(kind, err)
+    }
+}
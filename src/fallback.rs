@@ -0,0 +1,94 @@
+//! Entropy source falling back to `getrandom` where `AF_ALG` is unavailable
+
+use rand_core::{TryCryptoRng, TryRngCore};
+
+use crate::RandJitterKernel;
+
+/// entropy source chosen by [`RandJitterKernel::new_with_fallback`]
+#[derive(Debug)]
+pub enum EntropySource {
+    /// `jitterentropy_rng` inside the kernel via `AF_ALG`, boxed to keep the enum small
+    Jitter(Box<RandJitterKernel>),
+    /// the operating system rng via the `getrandom` crate
+    Getrandom,
+}
+
+impl RandJitterKernel {
+    /// constructs a `jitterentropy_rng` instance if possible, otherwise falls back to `getrandom`
+    ///
+    /// Any failure of [`RandJitterKernel::new`] triggers the fallback, e.g. `AF_ALG` being blocked
+    /// by seccomp in hardened containers.
+    ///
+    /// # Errors
+    /// Returns the error of `getrandom` if neither source works.
+    pub fn new_with_fallback() -> Result<EntropySource, std::io::Error> {
+        if let Ok(rng) = RandJitterKernel::new() {
+            return Ok(EntropySource::Jitter(Box::new(rng)));
+        }
+
+        // make sure the fallback works before handing it out
+        let mut probe = [0u8; 1];
+        getrandom::fill(&mut probe)?;
+        Ok(EntropySource::Getrandom)
+    }
+}
+
+impl From<RandJitterKernel> for EntropySource {
+    fn from(rng: RandJitterKernel) -> Self {
+        EntropySource::Jitter(Box::new(rng))
+    }
+}
+
+impl TryRngCore for EntropySource {
+    type Error = std::io::Error;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        match self {
+            Self::Jitter(rng) => rng.try_next_u32(),
+            Self::Getrandom => Ok(getrandom::u32()?),
+        }
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        match self {
+            Self::Jitter(rng) => rng.try_next_u64(),
+            Self::Getrandom => Ok(getrandom::u64()?),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        match self {
+            Self::Jitter(rng) => rng.try_fill_bytes(dst),
+            Self::Getrandom => Ok(getrandom::fill(dst)?),
+        }
+    }
+}
+
+/// both sources are intended for cryptographic use
+impl TryCryptoRng for EntropySource {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EntropySource, RandJitterKernel};
+    use rand_core::TryRngCore;
+
+    #[test]
+    fn test_new_with_fallback() {
+        let mut rng = RandJitterKernel::new_with_fallback().unwrap();
+        match &rng {
+            EntropySource::Jitter(_) => assert!(crate::is_available()),
+            EntropySource::Getrandom => assert!(RandJitterKernel::new().is_err()),
+        }
+        assert!(rng.try_next_u64().is_ok());
+    }
+
+    #[test]
+    fn test_getrandom_source() {
+        let mut rng = EntropySource::Getrandom;
+        let mut buf = [0u8; 300];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert!(buf.iter().any(|&b| b != 0));
+        assert!(rng.try_next_u32().is_ok());
+        assert!(rng.try_next_u64().is_ok());
+    }
+}
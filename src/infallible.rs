@@ -0,0 +1,94 @@
+//! Infallible `RngCore` wrapper for APIs that cannot handle errors
+
+use rand_core::{CryptoRng, RngCore, TryRngCore};
+
+use crate::RandJitterKernel;
+
+/// rng instance implementing the infallible [`RngCore`] trait
+///
+/// **Every method panics if reading from the kernel fails.** Only use this wrapper where an
+/// `RngCore` is required and a failing entropy source should abort, otherwise use the fallible
+/// [`TryRngCore`] methods of [`RandJitterKernel`] directly.
+#[derive(Debug)]
+pub struct InfallibleRandJitterKernel {
+    rng: RandJitterKernel,
+}
+
+impl InfallibleRandJitterKernel {
+    /// constructs new infallible RNG instance, only the methods of [`RngCore`] panic
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`].
+    pub fn new() -> Result<Self, std::io::Error> {
+        Ok(Self::from(RandJitterKernel::new()?))
+    }
+
+    /// returns the wrapped fallible instance
+    #[must_use]
+    pub fn into_inner(self) -> RandJitterKernel {
+        self.rng
+    }
+}
+
+impl From<RandJitterKernel> for InfallibleRandJitterKernel {
+    fn from(rng: RandJitterKernel) -> Self {
+        InfallibleRandJitterKernel { rng }
+    }
+}
+
+impl RngCore for InfallibleRandJitterKernel {
+    /// # Panics
+    /// Panics if no entropy could be read from the kernel.
+    fn next_u32(&mut self) -> u32 {
+        match self.rng.try_next_u32() {
+            Ok(value) => value,
+            Err(err) => panic!("unable to get entropy from jitterentropy_rng: {err}"),
+        }
+    }
+
+    /// # Panics
+    /// Panics if no entropy could be read from the kernel.
+    fn next_u64(&mut self) -> u64 {
+        match self.rng.try_next_u64() {
+            Ok(value) => value,
+            Err(err) => panic!("unable to get entropy from jitterentropy_rng: {err}"),
+        }
+    }
+
+    /// # Panics
+    /// Panics if no entropy could be read from the kernel.
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        if let Err(err) = self.rng.try_fill_bytes(dst) {
+            panic!("unable to get entropy from jitterentropy_rng: {err}");
+        }
+    }
+}
+
+impl CryptoRng for InfallibleRandJitterKernel {}
+
+#[cfg(test)]
+mod tests {
+    use crate::InfallibleRandJitterKernel;
+    use crate::test_support::pipe_rng;
+    use rand_core::RngCore;
+
+    #[test]
+    fn test_infallible() {
+        let mut rng = InfallibleRandJitterKernel::new().unwrap();
+        let _ = rng.next_u32();
+        let _ = rng.next_u64();
+        let mut buffer = [0u8; 300];
+        rng.fill_bytes(&mut buffer);
+    }
+
+    #[test]
+    #[should_panic(expected = "unable to get entropy from jitterentropy_rng")]
+    fn test_infallible_panics() {
+        // the write end is closed right away, so every read hits end of file
+        let (rng, write_fd) = pipe_rng(&[]);
+        drop(write_fd);
+
+        let mut rng = InfallibleRandJitterKernel::from(rng);
+        let _ = rng.next_u64();
+    }
+}
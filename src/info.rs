@@ -0,0 +1,85 @@
+//! Plain snapshot of the state of an rng instance for diagnostics
+
+use std::os::fd::RawFd;
+
+use crate::RandJitterKernel;
+
+/// state of an rng instance at the time of [`RandJitterKernel::snapshot`]
+///
+/// The snapshot does not own the descriptor, `fd` is only its number and may be closed or reused
+/// by the time it is read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InstanceInfo {
+    /// number of the rng descriptor, `None` if the instance is closed or reads from an injected
+    /// reader
+    pub fd: Option<RawFd>,
+    /// name of the rng algorithm, see [`RandJitterKernel::algorithm_name`]
+    pub algorithm: String,
+    /// number of bytes read so far, see [`RandJitterKernel::bytes_generated`]
+    pub bytes_generated: u64,
+    /// whether the instance was open, see [`RandJitterKernel::is_open`]
+    pub open: bool,
+}
+
+impl RandJitterKernel {
+    /// returns a snapshot of descriptor, algorithm name, byte counter and open state, e.g. for
+    /// structured logging
+    #[must_use]
+    pub fn snapshot(&self) -> InstanceInfo {
+        InstanceInfo {
+            fd: (self.rng_fd >= 0).then_some(self.rng_fd),
+            algorithm: self.options.algorithm.as_str().to_string(),
+            bytes_generated: self.bytes_generated(),
+            open: self.is_open(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::pipe_rng;
+    use crate::{InstanceInfo, RandJitterKernel};
+    use rand_core::TryRngCore;
+
+    #[test]
+    fn test_snapshot() {
+        use std::os::fd::FromRawFd;
+
+        let (mut rng, write_fd) = pipe_rng(&[0x42; 16]);
+        rng.try_fill_bytes(&mut [0u8; 16]).unwrap();
+        let info = rng.snapshot();
+        assert_eq!(
+            info,
+            InstanceInfo {
+                fd: Some(rng.rng_fd),
+                algorithm: "jitterentropy_rng".to_string(),
+                bytes_generated: 16,
+                open: true,
+            }
+        );
+
+        drop(write_fd);
+
+        let rng = unsafe { RandJitterKernel::from_raw_fd(-1) };
+        let info = rng.snapshot();
+        assert_eq!(info.fd, None);
+        assert!(!info.open);
+        assert_eq!(info.bytes_generated, 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_serialize() {
+        let info = InstanceInfo {
+            fd: Some(7),
+            algorithm: "jitterentropy_rng".to_string(),
+            bytes_generated: 128,
+            open: true,
+        };
+        assert_eq!(
+            serde_json::to_string(&info).unwrap(),
+            r#"{"fd":7,"algorithm":"jitterentropy_rng","bytes_generated":128,"open":true}"#
+        );
+    }
+}
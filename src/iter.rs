@@ -0,0 +1,86 @@
+//! Byte-wise iterator over kernel jitter entropy
+
+use rand_core::TryRngCore;
+
+use crate::{MAX_RETURN_CHUNK_SIZE, RandJitterKernel, wipe};
+
+/// iterator yielding one random byte at a time, created by [`RandJitterKernel::bytes_iter`]
+///
+/// Bytes are read from the kernel one chunk at a time and buffered. Pulling a byte fails if
+/// refilling the buffer fails, hence every item is a `Result`. The next call tries again.
+#[derive(Debug)]
+pub struct BytesIter<'a> {
+    rng: &'a mut RandJitterKernel,
+    buffer: [u8; MAX_RETURN_CHUNK_SIZE],
+    pos: usize,
+}
+
+impl<'a> BytesIter<'a> {
+    pub(crate) fn new(rng: &'a mut RandJitterKernel) -> Self {
+        BytesIter {
+            rng,
+            buffer: [0u8; MAX_RETURN_CHUNK_SIZE],
+            pos: MAX_RETURN_CHUNK_SIZE,
+        }
+    }
+}
+
+impl Iterator for BytesIter<'_> {
+    type Item = Result<u8, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.buffer.len() {
+            if let Err(err) = self.rng.try_fill_bytes(&mut self.buffer) {
+                return Some(Err(err));
+            }
+            self.pos = 0;
+        }
+
+        let byte = self.buffer[self.pos];
+        // consumed bytes must not stay around in the buffer
+        self.buffer[self.pos] = 0;
+        self.pos += 1;
+        Some(Ok(byte))
+    }
+}
+
+impl Drop for BytesIter<'_> {
+    fn drop(&mut self) {
+        wipe(&mut self.buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::pipe_rng;
+    use crate::{MAX_RETURN_CHUNK_SIZE, RandJitterKernel};
+
+    #[test]
+    fn test_bytes_iter() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        let bytes: Vec<u8> = rng
+            .bytes_iter()
+            .take(300)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(bytes.len(), 300);
+    }
+
+    #[test]
+    fn test_bytes_iter_refills() {
+        let data: Vec<u8> = (0..=255).collect();
+        let (mut rng, write_fd) = pipe_rng(&data);
+        drop(write_fd);
+
+        let mut iter = rng.bytes_iter();
+        let bytes: Vec<u8> = iter
+            .by_ref()
+            .take(2 * MAX_RETURN_CHUNK_SIZE)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(bytes, data);
+
+        // the pipe is drained, refilling fails
+        assert!(iter.next().unwrap().is_err());
+    }
+}
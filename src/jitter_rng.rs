@@ -0,0 +1,78 @@
+//! Zero-sized handle to a process-wide rng instance, used like `rand::rngs::OsRng`
+
+use std::sync::OnceLock;
+
+use rand_core::{TryCryptoRng, TryRngCore};
+
+use crate::SharedRandJitterKernel;
+
+/// process-wide instance, opened on first use
+static GLOBAL: OnceLock<SharedRandJitterKernel> = OnceLock::new();
+
+/// zero-sized handle to a lazily opened process-wide [`crate::RandJitterKernel`]
+///
+/// All handles share one descriptor behind a mutex. This keeps the descriptor count at one and
+/// makes every call after the first as cheap as a read, but serializes concurrent callers.
+/// Opening an ephemeral instance per call would avoid the lock at the cost of a
+/// `socket()`, `bind()` and `accept()` for every request. Threads with a hot path should own a
+/// [`crate::RandJitterKernel`] instead.
+///
+/// If opening the instance fails, the error is returned and the next call tries again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JitterRng;
+
+impl JitterRng {
+    fn global() -> Result<&'static SharedRandJitterKernel, std::io::Error> {
+        if let Some(rng) = GLOBAL.get() {
+            return Ok(rng);
+        }
+        // a racing thread may win, its instance is kept and this one is closed again
+        let rng = SharedRandJitterKernel::new()?;
+        Ok(GLOBAL.get_or_init(|| rng))
+    }
+}
+
+impl TryRngCore for JitterRng {
+    type Error = std::io::Error;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        Self::global()?.try_next_u32()
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        Self::global()?.try_next_u64()
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        Self::global()?.try_fill_bytes(dst)
+    }
+}
+
+impl TryCryptoRng for JitterRng {}
+
+#[cfg(test)]
+mod tests {
+    use super::JitterRng;
+    use rand_core::TryRngCore;
+
+    #[test]
+    fn test_jitter_rng() {
+        let mut buf = [0u8; 300];
+        JitterRng.try_fill_bytes(&mut buf).unwrap();
+        assert!(JitterRng.try_next_u64().is_ok());
+        assert!(std::ptr::eq(
+            JitterRng::global().unwrap(),
+            JitterRng::global().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_jitter_rng_threads() {
+        let threads: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(|| JitterRng.try_next_u32().unwrap()))
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Check of the running kernel version against the first one offering `jitterentropy_rng`
+
+use crate::RandJitterError;
+
+/// first kernel version with `jitterentropy_rng`, `AF_ALG` rng sockets arrived earlier in 4.0
+const MIN_KERNEL_VERSION: (u32, u32) = (4, 2);
+
+/// returns whether the running kernel is recent enough to offer `jitterentropy_rng`, which
+/// it does since Linux 4.2
+///
+/// Unlike [`crate::is_available`], this tells a kernel that is too old apart from a
+/// recent kernel that lacks the module or forbids `AF_ALG`. A supported version does not
+/// imply that `jitterentropy_rng` is built or loaded. Use [`check_kernel_support`] to get
+/// an error naming the running release instead.
+///
+/// # Errors
+/// Returns the OS error if `uname()` fails, an `InvalidData` error if the release string
+/// does not start with a version, and [`RandJitterError::Unsupported`] on other operating
+/// systems.
+pub fn kernel_supports() -> Result<bool, std::io::Error> {
+    Ok(kernel_version()?.1)
+}
+
+/// like [`kernel_supports`], but returns [`RandJitterError::KernelTooOld`] if the running
+/// kernel predates `jitterentropy_rng`
+///
+/// # Errors
+/// Same as [`kernel_supports`], plus [`RandJitterError::KernelTooOld`] wrapped inside a
+/// `std::io::Error`.
+pub fn check_kernel_support() -> Result<(), std::io::Error> {
+    match kernel_version()? {
+        (_, true) => Ok(()),
+        (release, false) => Err(RandJitterError::KernelTooOld { release }.into()),
+    }
+}
+
+/// returns the release string of the running kernel and whether it is supported
+fn kernel_version() -> Result<(String, bool), std::io::Error> {
+    let release = kernel_release()?;
+    let version = parse_kernel_version(&release).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("cannot parse kernel release {release:?}"),
+        )
+    })?;
+    Ok((release, version >= MIN_KERNEL_VERSION))
+}
+
+#[cfg(target_os = "linux")]
+fn kernel_release() -> Result<String, std::io::Error> {
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&raw mut uts) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // the kernel NUL-terminates release
+    let release = unsafe { std::ffi::CStr::from_ptr(uts.release.as_ptr()) };
+    Ok(release.to_string_lossy().into_owned())
+}
+
+/// `jitterentropy_rng` is only offered by the Linux kernel
+#[cfg(not(target_os = "linux"))]
+fn kernel_release() -> Result<String, std::io::Error> {
+    Err(RandJitterError::Unsupported.into())
+}
+
+/// parses major and minor version from a release like `6.8.0-45-generic`
+fn parse_kernel_version(release: &str) -> Option<(u32, u32)> {
+    let mut parts = release.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MIN_KERNEL_VERSION, check_kernel_support, kernel_supports, parse_kernel_version};
+    use crate::RandJitterError;
+
+    #[test]
+    fn test_parse_kernel_version() {
+        assert_eq!(parse_kernel_version("6.8.0-45-generic"), Some((6, 8)));
+        assert_eq!(parse_kernel_version("4.2"), Some((4, 2)));
+        assert_eq!(
+            parse_kernel_version("5.15.153.1-microsoft-standard-WSL2"),
+            Some((5, 15))
+        );
+        assert_eq!(
+            parse_kernel_version("3.10.0-1160.el7.x86_64"),
+            Some((3, 10))
+        );
+        assert_eq!(parse_kernel_version("6.10-rc1"), Some((6, 10)));
+        assert_eq!(parse_kernel_version("6"), None);
+        assert_eq!(parse_kernel_version("linux"), None);
+        assert_eq!(parse_kernel_version(""), None);
+    }
+
+    #[test]
+    fn test_min_kernel_version() {
+        assert!(parse_kernel_version("4.1.52").unwrap() < MIN_KERNEL_VERSION);
+        assert!(parse_kernel_version("4.2.0").unwrap() >= MIN_KERNEL_VERSION);
+        assert!(parse_kernel_version("10.0.1").unwrap() >= MIN_KERNEL_VERSION);
+    }
+
+    #[test]
+    fn test_kernel_supports() {
+        // every kernel able to build and run the test suite is newer than 4.2
+        assert!(kernel_supports().unwrap());
+        check_kernel_support().unwrap();
+    }
+
+    #[test]
+    fn test_kernel_too_old_message() {
+        let err = RandJitterError::KernelTooOld {
+            release: "3.10.0-1160.el7.x86_64".into(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "kernel 3.10.0-1160.el7.x86_64 is too old for jitterentropy_rng, which requires Linux 4.2 or newer"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Adapter for crates still on the infallible traits of `rand_core` 0.6
+
+use std::num::NonZeroU32;
+
+use rand_core::TryRngCore;
+
+use crate::RandJitterKernel;
+
+/// rng instance implementing the `RngCore` and `CryptoRng` traits of `rand_core` 0.6
+///
+/// **`next_u32`, `next_u64` and `fill_bytes` panic if reading from the kernel fails**, as the
+/// 0.6 traits offer no way to report it. Only `try_fill_bytes` returns the error, reduced to its
+/// OS error code. Migrate to [`TryRngCore`] where possible.
+#[derive(Debug)]
+pub struct LegacyRandJitterKernel {
+    rng: RandJitterKernel,
+}
+
+impl LegacyRandJitterKernel {
+    /// constructs new RNG instance for `rand_core` 0.6, only the infallible methods panic
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`].
+    pub fn new() -> Result<Self, std::io::Error> {
+        Ok(Self::from(RandJitterKernel::new()?))
+    }
+
+    /// returns the wrapped instance
+    #[must_use]
+    pub fn into_inner(self) -> RandJitterKernel {
+        self.rng
+    }
+}
+
+impl RandJitterKernel {
+    /// wraps this instance for `rand_core` 0.6, see [`LegacyRandJitterKernel`] for when it panics
+    #[must_use]
+    pub fn into_legacy(self) -> LegacyRandJitterKernel {
+        LegacyRandJitterKernel::from(self)
+    }
+}
+
+impl From<RandJitterKernel> for LegacyRandJitterKernel {
+    fn from(rng: RandJitterKernel) -> Self {
+        LegacyRandJitterKernel { rng }
+    }
+}
+
+/// first custom error code of `rand_core` 0.6, reported for errors without an OS error code
+const CUSTOM_ERROR_CODE: NonZeroU32 =
+    NonZeroU32::MIN.saturating_add(rand_core_06::Error::CUSTOM_START - 1);
+
+/// `rand_core` 0.6 only carries an error code without its `std` feature
+fn legacy_error(err: &std::io::Error) -> rand_core_06::Error {
+    let code = err
+        .raw_os_error()
+        .and_then(|code| u32::try_from(code).ok())
+        .and_then(NonZeroU32::new)
+        .unwrap_or(CUSTOM_ERROR_CODE);
+    rand_core_06::Error::from(code)
+}
+
+impl rand_core_06::RngCore for LegacyRandJitterKernel {
+    /// # Panics
+    /// Panics if no entropy could be read from the kernel.
+    fn next_u32(&mut self) -> u32 {
+        match self.rng.try_next_u32() {
+            Ok(value) => value,
+            Err(err) => panic!("unable to get entropy from jitterentropy_rng: {err}"),
+        }
+    }
+
+    /// # Panics
+    /// Panics if no entropy could be read from the kernel.
+    fn next_u64(&mut self) -> u64 {
+        match self.rng.try_next_u64() {
+            Ok(value) => value,
+            Err(err) => panic!("unable to get entropy from jitterentropy_rng: {err}"),
+        }
+    }
+
+    /// # Panics
+    /// Panics if no entropy could be read from the kernel.
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        if let Err(err) = self.rng.try_fill_bytes(dst) {
+            panic!("unable to get entropy from jitterentropy_rng: {err}");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), rand_core_06::Error> {
+        self.rng
+            .try_fill_bytes(dst)
+            .map_err(|err| legacy_error(&err))
+    }
+}
+
+impl rand_core_06::CryptoRng for LegacyRandJitterKernel {}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::pipe_rng;
+    use crate::{LegacyRandJitterKernel, RandJitterKernel};
+    use rand_core_06::RngCore;
+
+    #[test]
+    fn test_legacy_rng() {
+        let (rng, _write_fd) = pipe_rng(&[0x42; 16]);
+        let mut rng = rng.into_legacy();
+        let mut buf = [0u8; 8];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert_eq!(buf, [0x42; 8]);
+        assert_eq!(rng.next_u64(), 0x4242_4242_4242_4242);
+    }
+
+    #[test]
+    fn test_legacy_error_code() {
+        use std::os::fd::FromRawFd;
+
+        let mut rng = LegacyRandJitterKernel::from(unsafe { RandJitterKernel::from_raw_fd(-1) });
+        let err = rng.try_fill_bytes(&mut [0u8; 8]).unwrap_err();
+        assert_eq!(
+            err.code().map(std::num::NonZeroU32::get),
+            Some(rand_core_06::Error::CUSTOM_START)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unable to get entropy from jitterentropy_rng")]
+    fn test_legacy_panics() {
+        use std::os::fd::FromRawFd;
+
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(-1) }.into_legacy();
+        rng.next_u32();
+    }
+}
@@ -1,234 +1,3354 @@
 //! This crate provides an interface to the `jitterentropy_rng` inside the Linux kernel
 
-use rand_core::TryRngCore;
+use rand_core::{TryCryptoRng, TryRngCore};
+
+#[cfg(all(feature = "cpu-affinity", target_os = "linux"))]
+mod affinity;
+mod algorithm;
+#[cfg(feature = "tokio")]
+mod async_rng;
+mod buffer;
+mod builder;
+#[cfg(feature = "serde")]
+mod config;
+mod dyn_rng;
+mod error;
+#[cfg(feature = "fallback")]
+mod fallback;
+mod infallible;
+mod info;
+mod iter;
+mod jitter_rng;
+mod kernel;
+#[cfg(feature = "compat-legacy")]
+mod legacy;
+mod limit;
+mod per_thread;
+mod pool;
+#[cfg(feature = "prefetch")]
+mod prefetch;
+mod reader;
+mod shared;
+#[cfg(feature = "shared-cache")]
+mod shared_cache;
+#[cfg(feature = "stats")]
+mod stats;
+mod sys;
+#[cfg(test)]
+mod test_support;
+mod thread_rng;
+mod timeout;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+mod uring;
+
+#[cfg(feature = "tokio")]
+pub use async_rng::{AsyncRandJitterKernel, EntropyStream};
+pub use builder::{RandJitterKernelBuilder, SocketType};
+#[cfg(feature = "serde")]
+pub use config::RandJitterConfig;
+pub use dyn_rng::DynTryRng;
+pub use error::RandJitterError;
+#[cfg(feature = "fallback")]
+pub use fallback::EntropySource;
+pub use infallible::InfallibleRandJitterKernel;
+pub use info::InstanceInfo;
+pub use iter::BytesIter;
+pub use jitter_rng::JitterRng;
+pub use kernel::{check_kernel_support, kernel_supports};
+#[cfg(feature = "compat-legacy")]
+pub use legacy::LegacyRandJitterKernel;
+pub use per_thread::PerThread;
+pub use pool::RandJitterKernelPool;
+#[cfg(feature = "prefetch")]
+pub use prefetch::{DEFAULT_PREFETCH_DEPTH, PrefetchingRng};
+#[cfg(feature = "test-util")]
+pub use reader::EntropyReader;
+pub use shared::SharedRandJitterKernel;
+#[cfg(feature = "shared-cache")]
+pub use shared_cache::{DEFAULT_CACHE_ENTRIES, SharedEntropyCache};
+#[cfg(feature = "stats")]
+pub use stats::LatencyStats;
+pub use thread_rng::{ThreadLocalRng, thread_local_rng};
+pub use timeout::TimeoutGuard;
 
 const MAX_RETURN_CHUNK_SIZE: usize = 128;
 
+/// buffer size used to find out how many bytes the kernel returns for a single read
+const PROBE_READ_SIZE: usize = 4096;
+
+/// number of bytes read by [`RandJitterKernel::self_test`]
+const SELF_TEST_SAMPLE_SIZE: usize = 32;
+
+/// duration after which the probe read of [`RandJitterKernel::is_seeded`] counts as too slow
+const SEEDED_PROBE_THRESHOLD: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// number of bytes [`RandJitterKernel::warm_up`] discards
+const DEFAULT_WARM_UP_SIZE: usize = 4096;
+
+/// lowest descriptor number that is not stdin, stdout or stderr
+const MIN_NON_STDIO_FD: libc::c_int = 3;
+
+/// number of times a `read()` interrupted by a signal (`EINTR`) is retried before giving up
+const MAX_EINTR_RETRIES: usize = 16;
+
+/// number of `EAGAIN` retries of [`RandJitterKernel::try_fill_bytes_spin`] that only yield
+const SPIN_YIELDS: u32 = 4;
+
+/// first and longest sleep of [`RandJitterKernel::try_fill_bytes_spin`] once it stops yielding
+const SPIN_MIN_SLEEP: std::time::Duration = std::time::Duration::from_micros(10);
+const SPIN_MAX_SLEEP: std::time::Duration = std::time::Duration::from_millis(1);
+
 /// data structure holding state of the rng
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Instances compare by their rng descriptor. `Debug` only shows whether the descriptor is open,
+/// not its number.
+///
+/// An instance is `Send`, so it can be moved to another thread, and `Sync`, as `&self` methods
+/// only query the descriptor. Reading needs `&mut self`; to read from several threads, use one
+/// instance per thread or a [`SharedRandJitterKernel`].
 pub struct RandJitterKernel {
     rng_fd: libc::c_int,
+    max_chunk_size: usize,
+    options: builder::Options,
+    buffer: buffer::ReadBuffer,
+    bytes_generated: u64,
+    #[cfg(feature = "stats")]
+    latency: stats::LatencyStats,
+    /// replaces reads from `rng_fd` if set
+    reader: Option<Box<dyn reader::EntropyReader>>,
+    /// counts the instance against the instance limit, if it opened its descriptor itself
+    _slot: Option<limit::InstanceSlot>,
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    uring: uring::LazyRing,
 }
 
+// keep the thread-safety contract documented above from regressing
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+    assert_send::<RandJitterKernel>();
+    assert_sync::<RandJitterKernel>();
+    assert_send::<SharedRandJitterKernel>();
+    assert_sync::<SharedRandJitterKernel>();
+    assert_send::<RandJitterKernelPool>();
+    assert_sync::<RandJitterKernelPool>();
+    assert_send::<InfallibleRandJitterKernel>();
+    assert_send::<JitterRng>();
+    assert_sync::<JitterRng>();
+    assert_send::<RandJitterError>();
+    assert_sync::<RandJitterError>();
+};
+
 impl RandJitterKernel {
+    /// maximum number of bytes the kernel returns for a single read, as implemented by
+    /// `algif_rng` today; every instance starts with this limit
+    pub const MAX_CHUNK_SIZE: usize = MAX_RETURN_CHUNK_SIZE;
+
     /// constructs new RNG instance
     ///
     /// # Errors
-    /// For all used errors, a different string reason is returned inside `std::io::Error::other(..)`.
+    /// Every failure is reported as a [`RandJitterError`] wrapped inside a `std::io::Error`.
+    /// It can be retrieved again via `std::io::Error::get_ref` and `downcast_ref`.
+    /// If a syscall failed, the `std::io::Error` carries its `ErrorKind` and
+    /// [`RandJitterError::raw_os_error`] returns the errno.
+    /// On targets other than Linux, [`RandJitterError::Unsupported`] is returned.
     pub fn new() -> Result<Self, std::io::Error> {
+        Self::builder().build()
+    }
+
+    /// constructs new RNG instance with an internal buffer of `bytes` for small requests, see
+    /// [`RandJitterKernelBuilder::buffer_capacity`]
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernelBuilder::build`].
+    pub fn with_buffer_capacity(bytes: usize) -> Result<Self, std::io::Error> {
+        Self::builder().buffer_capacity(bytes).build()
+    }
+
+    /// constructs new RNG instance of any rng algorithm the kernel offers via `AF_ALG`,
+    /// e.g. `drbg_nopr_hmac_sha256`
+    ///
+    /// [`RandJitterKernel::new`] is equivalent to `with_algorithm("jitterentropy_rng")`.
+    ///
+    /// # Errors
+    /// Returns [`RandJitterError::AlgorithmNameTooLong`] if `name` is longer than 63 byte, as
+    /// the 64 byte `salg_name` field needs room for the terminating NUL, and
+    /// [`RandJitterError::InvalidAlgorithmName`] if it is empty or contains a NUL byte.
+    /// Otherwise the same as [`RandJitterKernel::new`].
+    pub fn with_algorithm(name: &str) -> Result<Self, std::io::Error> {
+        let options = builder::Options {
+            algorithm: algorithm::AlgorithmName::new(name)?,
+            ..builder::Options::default()
+        };
+        Ok(Self::open(&options)?)
+    }
+
+    /// returns a builder to construct an instance with non-default options
+    #[must_use]
+    pub fn builder() -> RandJitterKernelBuilder {
+        RandJitterKernelBuilder::new()
+    }
+
+    /// limits the number of instances open at the same time in this process to `max`, `usize::MAX`
+    /// (the default) disables the limit
+    ///
+    /// Instances created by `new()`, the builder, a [`RandJitterKernelPool`] or `try_clone` count
+    /// against the limit until dropped, instances created by `from_raw_fd` do not. Reopening an
+    /// instance keeps its place. Lowering the limit below the number of open instances only
+    /// affects new instances.
+    pub fn set_max_instances(max: usize) {
+        limit::GLOBAL_LIMIT.set_max(max);
+    }
+
+    /// returns the number of instances currently counted against the instance limit
+    #[must_use]
+    pub fn open_instances() -> usize {
+        limit::GLOBAL_LIMIT.open()
+    }
+
+    /// constructs an instance reading from `reader` instead of the kernel, e.g. to test error
+    /// handling deterministically
+    ///
+    /// The instance holds no descriptor, so `try_clone`, `reopen` and `check` fail and
+    /// `as_raw_fd` returns -1.
+    #[cfg(feature = "test-util")]
+    pub fn from_reader(reader: impl reader::EntropyReader + 'static) -> Self {
+        Self::with_reader(Box::new(reader))
+    }
+
+    #[cfg(any(test, feature = "test-util"))]
+    fn with_reader(reader: Box<dyn reader::EntropyReader>) -> Self {
+        RandJitterKernel {
+            rng_fd: -1,
+            max_chunk_size: MAX_RETURN_CHUNK_SIZE,
+            options: builder::Options::default(),
+            buffer: buffer::ReadBuffer::new(),
+            bytes_generated: 0,
+            #[cfg(feature = "stats")]
+            latency: stats::LatencyStats::default(),
+            reader: Some(reader),
+            _slot: None,
+            #[cfg(all(feature = "io_uring", target_os = "linux"))]
+            uring: uring::LazyRing::default(),
+        }
+    }
+
+    pub(crate) fn open(options: &builder::Options) -> Result<Self, RandJitterError> {
         /*
          * We need to open a socket to declare the algorithm to be used first (fam_fd).
          * In a next step, we accept on this socket to get a specific instance (rng_fd).
          * After getting the instance, we can close fam_fd.
          */
 
-        // AF_ALG with jitterentropy_rng is currently only implemented inside the Linux kernel
-        #[cfg(not(target_os = "linux"))]
-        compile_error!("Only Linux is supported");
+        Self::open_with_slot(options, Some(limit::acquire()?))
+    }
 
-        // close this on every (early) return!
-        let fam_fd = unsafe { libc::socket(libc::AF_ALG, libc::SOCK_SEQPACKET, 0) };
-        if fam_fd < 0 {
-            return Err(std::io::Error::other(
-                "unable to create AF_ALG socket for jitterentropy_rng",
-            ));
-        }
+    /// like [`Self::open`], but counts the instance with `slot` instead of taking a new one
+    fn open_with_slot(
+        options: &builder::Options,
+        slot: Option<limit::InstanceSlot>,
+    ) -> Result<Self, RandJitterError> {
+        let buffer = Self::alloc_buffer(options)?;
+        let fam_fd = open_family_fd(options.cloexec, &options.algorithm, options.socket_type)?;
+        let rng = Self::accept_with_slot(fam_fd, options, slot, buffer);
 
-        let mut sock_addr: libc::sockaddr_alg = unsafe { std::mem::zeroed() };
-        sock_addr.salg_family = u16::try_from(libc::AF_ALG)
-            .map_err(|_| std::io::Error::other("unable to convert socket algorithm family"))?;
-        let rng_type = "rng";
-        let rng_name = "jitterentropy_rng";
+        // as we now got the specific rng_fd instance, we can close the fd announcing the type of algorithm
+        // we are interested in
+        sys::close(fam_fd);
 
-        sock_addr.salg_type[..rng_type.len()].copy_from_slice(rng_type.to_string().as_bytes());
-        sock_addr.salg_name[..rng_name.len()].copy_from_slice(rng_name.to_string().as_bytes());
+        rng
+    }
 
-        let bind_ret = unsafe {
-            libc::bind(
-                fam_fd,
-                std::ptr::addr_of!(sock_addr).cast::<libc::sockaddr>(),
-                u32::try_from(std::mem::size_of_val(&sock_addr))
-                    .map_err(|_| std::io::Error::other("unable to convert size of sock_addr"))?,
-            )
-        };
-        if bind_ret != 0 {
-            unsafe {
-                libc::close(fam_fd);
-            }
-            return Err(std::io::Error::other("unable to bind AF_ALG socket"));
-        }
+    /// creates an `AF_ALG` socket bound to `jitterentropy_rng`, to be passed to
+    /// [`RandJitterKernel::from_family_fd`] later
+    ///
+    /// Creating the socket early allows to construct instances after dropping the privileges
+    /// or sandboxing, which may forbid creating new sockets.
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`].
+    pub fn create_family_fd() -> Result<std::os::fd::OwnedFd, std::io::Error> {
+        let fam_fd = open_family_fd(
+            true,
+            &algorithm::AlgorithmName::default(),
+            SocketType::default(),
+        )?;
+        Ok(unsafe { std::os::fd::FromRawFd::from_raw_fd(fam_fd) })
+    }
 
-        let rng_fd = unsafe { libc::accept(fam_fd, std::ptr::null_mut(), std::ptr::null_mut()) };
-        if rng_fd < 0 {
-            unsafe {
-                libc::close(fam_fd);
-            }
-            return Err(std::io::Error::other("unable to get rng_fd from kernel"));
-        }
+    /// constructs new RNG instance by accepting on the bound family socket `fam_fd`, e.g.
+    /// created by [`RandJitterKernel::create_family_fd`]
+    ///
+    /// `fam_fd` stays open and owned by the caller, so it can be used for further instances.
+    ///
+    /// # Errors
+    /// Returns [`RandJitterError::Accept`] if `fam_fd` is no bound `AF_ALG` socket or the kernel
+    /// does not hand out another instance. Otherwise the same as [`RandJitterKernel::new`].
+    pub fn from_family_fd(fam_fd: std::os::fd::RawFd) -> Result<Self, std::io::Error> {
+        Ok(Self::accept_from(fam_fd, &builder::Options::default())?)
+    }
 
-        // as we now got the specific rng_fd instance, we can close the fd announcing the type of algorithm
-        // we are interested in
-        unsafe { libc::close(fam_fd) };
+    /// accepts a new rng instance on a bound family socket, which is left open
+    pub(crate) fn accept_from(
+        fam_fd: libc::c_int,
+        options: &builder::Options,
+    ) -> Result<Self, RandJitterError> {
+        let buffer = Self::alloc_buffer(options)?;
+        Self::accept_with_slot(fam_fd, options, Some(limit::acquire()?), buffer)
+    }
 
-        Ok(RandJitterKernel { rng_fd })
+    /// allocates the buffer of a new instance before any descriptor, so a capacity which cannot be
+    /// allocated fails without touching the kernel
+    fn alloc_buffer(options: &builder::Options) -> Result<buffer::ReadBuffer, RandJitterError> {
+        buffer::ReadBuffer::with_chunks(options.buffer_capacity, MAX_RETURN_CHUNK_SIZE)
     }
 
-    fn try_fill_bytes_max_chunk_size(&mut self, dst: &mut [u8]) -> Result<(), std::io::Error> {
-        if dst.len() > MAX_RETURN_CHUNK_SIZE {
-            return Err(std::io::Error::other(format!(
-                "Cannot return more than {} byte in a single call. Requested: {} byte",
-                MAX_RETURN_CHUNK_SIZE,
-                dst.len()
-            )));
+    /// like [`Self::accept_from`], but counts the instance with `slot` instead of taking a new one
+    fn accept_with_slot(
+        fam_fd: libc::c_int,
+        options: &builder::Options,
+        slot: Option<limit::InstanceSlot>,
+        buffer: buffer::ReadBuffer,
+    ) -> Result<Self, RandJitterError> {
+        let mut rng_fd = accept_rng_fd(fam_fd, options.cloexec)?;
+        if options.avoid_stdio_fds {
+            rng_fd = move_fd_above(rng_fd, MIN_NON_STDIO_FD, options.cloexec)
+                .map_err(RandJitterError::SetOption)?;
         }
 
-        if self.rng_fd < 0 {
-            return Err(std::io::Error::other(format!(
-                "Cannot get entropy from jitterentropy_rng in kernel with invalid fd {}",
-                self.rng_fd
-            )));
-        }
-
-        let size = unsafe {
-            libc::read(
-                self.rng_fd,
-                dst.as_mut_ptr().cast::<libc::c_void>(),
-                dst.len(),
-            )
+        // the instance closes rng_fd again if applying the options fails
+        let mut rng = RandJitterKernel {
+            rng_fd,
+            max_chunk_size: MAX_RETURN_CHUNK_SIZE,
+            options: *options,
+            buffer,
+            bytes_generated: 0,
+            #[cfg(feature = "stats")]
+            latency: stats::LatencyStats::default(),
+            reader: None,
+            _slot: slot,
+            #[cfg(all(feature = "io_uring", target_os = "linux"))]
+            uring: uring::LazyRing::default(),
         };
-
-        if size >= 0
-            && usize::try_from(size)
-                .map_err(|_| std::io::Error::other("unable to convert returned size to usize"))?
-                == dst.len()
-        {
-            Ok(())
-        } else {
-            Err(std::io::Error::other(
-                "Cannot get entropy from jitterentropy_rng in kernel",
-            ))
+        // the timeout also bounds the warm-up reads
+        if options.read_timeout.is_some() {
+            set_fd_read_timeout(rng_fd, options.read_timeout)
+                .map_err(RandJitterError::SetOption)?;
+        }
+        // warming up has to wait for the kernel
+        if options.warm_up {
+            rng.warm_up_raw(DEFAULT_WARM_UP_SIZE)?;
         }
+        if options.nonblocking {
+            set_fd_nonblocking(rng_fd, true).map_err(RandJitterError::SetOption)?;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            max_chunk_size = rng.max_chunk_size,
+            "opened jitterentropy_rng instance"
+        );
+        Ok(rng)
     }
-}
 
-impl Default for RandJitterKernel {
-    fn default() -> Self {
-        Self::new().unwrap()
+    /// non-panicking counterpart to [`Default::default`], constructs a new RNG instance
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`].
+    pub fn try_default() -> Result<Self, std::io::Error> {
+        Self::new()
     }
-}
 
-impl Drop for RandJitterKernel {
-    fn drop(&mut self) {
-        assert!(self.rng_fd >= 0, "rng_fd already closed or never opened?");
-        unsafe {
-            libc::close(self.rng_fd);
-        }
-        self.rng_fd = -1;
+    /// maximum number of bytes the kernel returns for a single read of this instance
+    ///
+    /// Instances start with [`RandJitterKernel::MAX_CHUNK_SIZE`], so constructing one reads no
+    /// entropy. [`RandJitterKernel::probe_max_read`] raises the value if the kernel returns more.
+    #[must_use]
+    pub fn max_chunk_size(&self) -> usize {
+        self.max_chunk_size
     }
-}
 
-impl TryRngCore for RandJitterKernel {
-    type Error = std::io::Error;
+    /// issues a single 4 KiB `read()` and returns how many bytes the kernel returned, which is
+    /// the current limit per read unless the source delivered less
+    ///
+    /// A larger result raises [`RandJitterKernel::max_chunk_size`] to it, a smaller one leaves it
+    /// unchanged, as a read may also return early, e.g. when interrupted by a signal. The bytes
+    /// read are wiped and discarded.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn probe_max_read(&mut self) -> Result<usize, std::io::Error> {
+        let mut probe = [0u8; PROBE_READ_SIZE];
+        let result = self.read_once(&mut probe);
+        wipe(&mut probe);
+        let size = result?;
+        self.max_chunk_size = self.max_chunk_size.max(size);
+        Ok(size)
+    }
 
-    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
-        Ok(u32::try_from(self.try_next_u64()? & 0xFF_FF_FF_FF).unwrap())
+    /// returns whether this instance still holds a descriptor or an injected reader, without
+    /// asking the kernel
+    #[must_use]
+    pub fn is_open(&self) -> bool {
+        self.rng_fd >= 0 || self.reader.is_some()
     }
 
-    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
-        let mut bytes: [u8; 8] = [0; 8];
-        self.try_fill_bytes(&mut bytes)?;
+    /// confirms via `fcntl(F_GETFD)` that the kernel still considers the descriptor valid,
+    /// without reading entropy
+    ///
+    /// # Errors
+    /// Returns [`RandJitterError::InvalidFd`] wrapped inside a `std::io::Error` if the instance
+    /// holds no descriptor or the kernel rejects it.
+    pub fn check(&self) -> Result<(), std::io::Error> {
+        if !self.is_open() || unsafe { libc::fcntl(self.rng_fd, libc::F_GETFD) } < 0 {
+            return Err(RandJitterError::InvalidFd.into());
+        }
+        Ok(())
+    }
+
+    /// returns whether the kernel source looks ready, judged by how fast a probe read returns
+    ///
+    /// Neither `algif_rng` nor `jitterentropy_rng` expose a readiness signal: the source runs its
+    /// health tests when the kernel registers it and an unhealthy source fails reads instead.
+    /// As a heuristic, a 32 byte probe read taking longer than 100 ms, e.g. while the kernel is
+    /// still warming up after boot, returns `false`. The probe bytes are discarded.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn is_seeded(&mut self) -> Result<bool, std::io::Error> {
+        self.is_seeded_within(SEEDED_PROBE_THRESHOLD)
+    }
 
-        Ok(u64::from_ne_bytes(bytes))
+    fn is_seeded_within(&mut self, threshold: std::time::Duration) -> Result<bool, std::io::Error> {
+        let mut probe = [0u8; SELF_TEST_SAMPLE_SIZE];
+        let start = std::time::Instant::now();
+        let result = self.try_fill_bytes(&mut probe);
+        let elapsed = start.elapsed();
+        wipe(&mut probe);
+
+        result?;
+        Ok(elapsed <= threshold)
     }
 
-    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
-        let mut idx = 0;
-        while idx < dst.len() {
-            let chunk_size = if idx + MAX_RETURN_CHUNK_SIZE > dst.len() {
-                dst.len() - idx
-            } else {
-                MAX_RETURN_CHUNK_SIZE
-            };
-            self.try_fill_bytes_max_chunk_size(&mut dst[idx..idx + chunk_size])?;
-            idx += chunk_size;
+    /// returns the name of the rng algorithm this instance was opened with
+    ///
+    /// The kernel does not report the algorithm bound to an `AF_ALG` socket, its `getsockname()`
+    /// fails with `EOPNOTSUPP` and there is no socket option for it. So this is the name stored
+    /// at construction, which the kernel matched exactly or by driver name when binding. Instances
+    /// created by `from_raw_fd` or from a reader report `jitterentropy_rng`.
+    ///
+    /// # Errors
+    /// Returns [`RandJitterError::InvalidFd`] if the instance is closed.
+    pub fn algorithm_name(&self) -> Result<String, std::io::Error> {
+        if !self.is_open() {
+            return Err(RandJitterError::InvalidFd.into());
         }
-        assert_eq!(idx, dst.len());
+        Ok(self.options.algorithm.as_str().to_string())
+    }
 
-        Ok(())
+    /// reads and discards 4 KiB to exercise the kernel jitter collector before first real use
+    ///
+    /// This may block while the kernel collects jitter. Use
+    /// [`RandJitterKernel::warm_up_bytes`] to read a different amount.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn warm_up(&mut self) -> Result<(), std::io::Error> {
+        self.warm_up_bytes(DEFAULT_WARM_UP_SIZE)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::RandJitterKernel;
-    use rand_core::TryRngCore;
+    /// reads and discards `n` bytes, like [`RandJitterKernel::warm_up`]
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn warm_up_bytes(&mut self, n: usize) -> Result<(), std::io::Error> {
+        Ok(self.warm_up_raw(n)?)
+    }
 
-    #[test]
-    fn test_u32() {
-        let mut rng = RandJitterKernel::new().unwrap();
-        for _ in 0..1000 {
-            let u = rng.try_next_u32();
-            assert!(u.is_ok());
+    fn warm_up_raw(&mut self, n: usize) -> Result<(), RandJitterError> {
+        let mut chunk = [0u8; MAX_RETURN_CHUNK_SIZE];
+        let mut remaining = n;
+        let result = loop {
+            if remaining == 0 {
+                break Ok(());
+            }
+            let len = remaining.min(self.max_chunk_size).min(chunk.len());
+            if let Err(err) = self.try_fill_bytes_max_chunk_size(&mut chunk[..len]) {
+                break Err(err);
+            }
+            remaining -= len;
+        };
+        wipe(&mut chunk);
+        result
+    }
+
+    /// reads a 32 byte sample and rejects it if it is all zero, cheap enough for a startup or
+    /// readiness probe
+    ///
+    /// An all-zero sample from a working source has a probability of 2^-256, so it is treated
+    /// as a broken source rather than bad luck.
+    ///
+    /// # Errors
+    /// Returns [`RandJitterError::SelfTestFailed`] wrapped inside a `std::io::Error` for an
+    /// all-zero sample, otherwise the same as [`TryRngCore::try_fill_bytes`].
+    pub fn self_test(&mut self) -> Result<(), std::io::Error> {
+        let mut sample = [0u8; SELF_TEST_SAMPLE_SIZE];
+        let result = self.try_fill_bytes(&mut sample);
+        let all_zero = sample.iter().all(|&b| b == 0);
+        wipe(&mut sample);
+
+        result?;
+        if all_zero {
+            return Err(RandJitterError::SelfTestFailed.into());
         }
+        Ok(())
     }
 
-    #[test]
-    fn test_u64() {
-        let mut rng = RandJitterKernel::new().unwrap();
-        for _ in 0..1000 {
-            let u = rng.try_next_u64();
-            assert!(u.is_ok());
+    /// allocates and returns `n` random bytes
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn random_bytes(&mut self, n: usize) -> Result<Vec<u8>, std::io::Error> {
+        if n == 0 {
+            return Ok(Vec::new());
         }
+
+        let mut bytes = vec![0u8; n];
+        self.try_fill_bytes(&mut bytes)?;
+        Ok(bytes)
     }
 
-    #[test]
-    fn test_speed() {
-        use std::time::Instant;
-        let start = Instant::now();
-        let mut num_bytes = 0usize;
-        let mut rng = RandJitterKernel::new().unwrap();
+    /// returns a random `u32` decoded from little-endian bytes
+    ///
+    /// For entropy, the native-endian [`TryRngCore::try_next_u32`] is just as good. Use the
+    /// explicit variants where the same bytes must give the same value on every host, e.g. to
+    /// derive values that are reproduced on another machine.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u32`].
+    pub fn try_next_u32_le(&mut self) -> Result<u32, std::io::Error> {
+        self.next_from_bytes(u32::from_le_bytes)
+    }
 
-        loop {
-            let mut b = [0u8; 32];
-            rng.try_fill_bytes(&mut b).unwrap();
+    /// returns a random `u32` decoded from big-endian bytes, see
+    /// [`RandJitterKernel::try_next_u32_le`]
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u32`].
+    pub fn try_next_u32_be(&mut self) -> Result<u32, std::io::Error> {
+        self.next_from_bytes(u32::from_be_bytes)
+    }
 
-            let now = Instant::now();
+    /// returns a random `u64` decoded from little-endian bytes, see
+    /// [`RandJitterKernel::try_next_u32_le`]
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u64`].
+    pub fn try_next_u64_le(&mut self) -> Result<u64, std::io::Error> {
+        self.next_from_bytes(u64::from_le_bytes)
+    }
 
-            num_bytes += b.len();
+    /// returns a random `u64` decoded from big-endian bytes, see
+    /// [`RandJitterKernel::try_next_u32_le`]
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u64`].
+    pub fn try_next_u64_be(&mut self) -> Result<u64, std::io::Error> {
+        self.next_from_bytes(u64::from_be_bytes)
+    }
 
-            if (now - start).as_secs() > 2 {
-                let datarate = f64::from(u32::try_from(num_bytes).unwrap())
-                    / (now - start).as_secs_f64()
-                    / 1024.0;
-                println!("datarate: {datarate} KiB/s");
-                break;
+    /// takes `N` bytes from the buffer and converts them, the bytes are wiped afterwards
+    fn next_from_bytes<T, const N: usize>(
+        &mut self,
+        convert: fn([u8; N]) -> T,
+    ) -> Result<T, std::io::Error> {
+        let mut bytes = [0u8; N];
+        let result = self.fill_buffered(&mut bytes);
+        let value = convert(bytes);
+        wipe(&mut bytes);
+
+        Ok(result.map(|()| value)?)
+    }
+
+    /// returns a uniformly distributed `f64` in `[0.0, 1.0)`
+    ///
+    /// The top 53 bits of a random `u64` are taken as an integer `x` and scaled to
+    /// `x * 2^-53`, so every multiple of `2^-53` in the interval is equally likely.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u64`].
+    // x < 2^53 fits into the mantissa, so the conversion is exact
+    #[allow(clippy::cast_precision_loss)]
+    pub fn try_next_f64(&mut self) -> Result<f64, std::io::Error> {
+        let x = self.try_next_u64()? >> 11;
+        Ok(x as f64 * (1.0 / (1u64 << 53) as f64))
+    }
+
+    /// returns a uniformly distributed `f32` in `[0.0, 1.0)`
+    ///
+    /// The top 24 bits of a random `u32` are taken as an integer `x` and scaled to
+    /// `x * 2^-24`, so every multiple of `2^-24` in the interval is equally likely.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u32`].
+    // x < 2^24 fits into the mantissa, so the conversion is exact
+    #[allow(clippy::cast_precision_loss)]
+    pub fn try_next_f32(&mut self) -> Result<f32, std::io::Error> {
+        let x = self.try_next_u32()? >> 8;
+        Ok(x as f32 * (1.0 / (1u32 << 24) as f32))
+    }
+
+    /// returns `true` or `false` with equal probability, taken from one bit of a fresh byte
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn try_gen_bool(&mut self) -> Result<bool, std::io::Error> {
+        let mut byte = [0u8; 1];
+        self.fill_buffered(&mut byte)?;
+        let bit = byte[0] & 1 == 1;
+        wipe(&mut byte);
+        Ok(bit)
+    }
+
+    /// returns a uniformly distributed `u64` in `range`
+    ///
+    /// Values from the biased top end of the `u64` domain are rejected and drawn again, so there
+    /// is no modulo bias. Less than half of all draws are rejected, whatever the range.
+    ///
+    /// # Errors
+    /// Returns [`RandJitterError::EmptyRange`] wrapped inside a `std::io::Error` if `range` is
+    /// empty, otherwise the same as [`TryRngCore::try_next_u64`].
+    pub fn try_gen_range_u64(
+        &mut self,
+        range: std::ops::Range<u64>,
+    ) -> Result<u64, std::io::Error> {
+        if range.is_empty() {
+            return Err(RandJitterError::EmptyRange.into());
+        }
+
+        let span = range.end - range.start;
+        // 2^64 mod span, the draws below it would make small results more likely
+        let threshold = span.wrapping_neg() % span;
+        loop {
+            let x = self.try_next_u64()?;
+            if x >= threshold {
+                return Ok(range.start + x % span);
             }
         }
     }
 
-    #[test]
-    fn test_bytes() {
-        let mut rng = RandJitterKernel::new().unwrap();
+    /// fills the entire `dst` with random bytes or returns an error
+    ///
+    /// Unlike [`std::io::Read::read`], which returns at most one chunk per call, this never
+    /// returns after a partial fill. It reads chunk by chunk like [`TryRngCore::try_fill_bytes`].
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`]. On error, `dst` may be partially filled.
+    pub fn fill_exact(&mut self, dst: &mut [u8]) -> Result<(), std::io::Error> {
+        self.try_fill_bytes(dst)
+    }
 
-        for buffer_size in 0..=256 {
-            let mut buffer = vec![0u8; buffer_size];
-            assert!(rng.try_fill_bytes(&mut buffer).is_ok());
-            println!("{buffer_size}: {buffer:#04X?}");
+    /// fills `dst` with random bytes like [`TryRngCore::try_fill_bytes`], but reports how far it got
+    ///
+    /// Returns `dst.len()` on success. On error, the number of bytes written to the start of `dst`
+    /// is returned alongside the error, so a fill can be resumed with the rest of the buffer.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn try_fill_bytes_partial(
+        &mut self,
+        dst: &mut [u8],
+    ) -> Result<usize, (usize, std::io::Error)> {
+        let mut idx = 0;
+        while idx < dst.len() {
+            let chunk_size = self.max_chunk_size.min(dst.len() - idx);
+            self.try_fill_bytes_max_chunk_size(&mut dst[idx..idx + chunk_size])
+                .map_err(|err| (idx, err.into()))?;
+            idx += chunk_size;
         }
+        Ok(idx)
     }
 
-    #[test]
-    fn test_large_bytes_but_ok() {
-        let mut rng = RandJitterKernel::new().unwrap();
-        let mut buffer = [0u8; 128];
-        assert!(rng.try_fill_bytes_max_chunk_size(&mut buffer).is_ok());
+    /// issues a single `read()` of up to one chunk into `dst` and returns how many bytes it
+    /// returned, which may be fewer than requested
+    ///
+    /// Unlike [`std::io::Read::read`], which completes a short read up to one chunk, this mirrors
+    /// POSIX `read()`, e.g. as the primitive of a buffered reader.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn try_read_some(&mut self, dst: &mut [u8]) -> Result<usize, std::io::Error> {
+        let len = dst.len().min(self.max_chunk_size);
+        Ok(self.read_once(&mut dst[..len])?)
     }
 
-    #[test]
-    fn test_too_large_bytes() {
-        let mut rng = RandJitterKernel::new().unwrap();
-        let mut buffer = [0u8; 129];
-        assert!(rng.try_fill_bytes_max_chunk_size(&mut buffer).is_err());
+    /// fills `dst` with random bytes on a non-blocking descriptor, retrying reads that fail with
+    /// `EAGAIN` at most `max_spins` times in total
+    ///
+    /// The first retries only yield the thread, later ones sleep for 10 µs, doubling up to 1 ms.
+    /// This trades CPU time and latency for not needing a reactor: yielding keeps a core busy
+    /// while other threads are idle, and sleeping may oversleep by up to 1 ms once data arrives.
+    /// Prefer a blocking descriptor or [`crate::AsyncRandJitterKernel`] where possible. On a
+    /// blocking descriptor this behaves like [`TryRngCore::try_fill_bytes`].
+    ///
+    /// # Errors
+    /// Returns a `WouldBlock` error once `max_spins` retries are used up, otherwise the same as
+    /// [`TryRngCore::try_fill_bytes`]. On error, `dst` may be partially filled.
+    pub fn try_fill_bytes_spin(
+        &mut self,
+        dst: &mut [u8],
+        max_spins: u32,
+    ) -> Result<(), std::io::Error> {
+        let mut idx = 0;
+        let mut spins = 0;
+        let mut sleep = SPIN_MIN_SLEEP;
+        while idx < dst.len() {
+            let end = idx.saturating_add(self.max_chunk_size).min(dst.len());
+            match self.read_once(&mut dst[idx..end]) {
+                Ok(size) => idx += size,
+                Err(RandJitterError::Read(err))
+                    if err.kind() == std::io::ErrorKind::WouldBlock && spins < max_spins =>
+                {
+                    spins += 1;
+                    if spins <= SPIN_YIELDS {
+                        std::thread::yield_now();
+                    } else {
+                        std::thread::sleep(sleep);
+                        sleep = (sleep * 2).min(SPIN_MAX_SLEEP);
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// fills `dst` with random bytes, requesting all remaining bytes in every `read()` instead of
+    /// at most one chunk
+    ///
+    /// `recv()` and `recvmsg()` without flags behave like `read()` on the socket, and the kernel
+    /// caps every one of them at the chunk size inside `algif_rng` (128 byte). So on current
+    /// kernels this issues as many syscalls as [`TryRngCore::try_fill_bytes`] and only saves
+    /// syscalls on kernels or sources returning larger messages.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`]. On error, `dst` may be partially filled.
+    pub fn try_fill_bytes_bulk(&mut self, dst: &mut [u8]) -> Result<(), std::io::Error> {
+        let mut filled = 0;
+        while filled < dst.len() {
+            filled += self.read_once(&mut dst[filled..])?;
+        }
+        Ok(())
+    }
+
+    /// fills `dst` with random `u64` values in one go, e.g. the state of a xoshiro generator
+    ///
+    /// The bytes are read into the memory of `dst`, so every value is native-endian like one
+    /// from [`TryRngCore::try_next_u64`]. Convert with [`u64::to_le`] where the values must be
+    /// reproducible from the same bytes on hosts of either endianness.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`]. On error, `dst` may be partially filled.
+    pub fn try_fill_u64(&mut self, dst: &mut [u64]) -> Result<(), std::io::Error> {
+        // u8 has no alignment requirement and every bit pattern is a valid u64
+        let bytes = unsafe {
+            std::slice::from_raw_parts_mut(dst.as_mut_ptr().cast::<u8>(), size_of_val(dst))
+        };
+        self.try_fill_bytes(bytes)
+    }
+
+    /// fills the uninitialized `dst` with random bytes and returns it as initialized slice,
+    /// which saves zeroing large buffers before they are filled
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`]. On error, `dst` may be partially initialized.
+    pub fn try_fill_uninit<'a>(
+        &mut self,
+        dst: &'a mut [std::mem::MaybeUninit<u8>],
+    ) -> Result<&'a mut [u8], std::io::Error> {
+        // injected readers get a slice, which must not point to uninitialized memory
+        if self.reader.is_some() {
+            dst.fill(std::mem::MaybeUninit::new(0));
+        }
+
+        let ptr = dst.as_mut_ptr().cast::<u8>();
+        let mut filled = 0;
+        while filled < dst.len() {
+            let len = (dst.len() - filled).min(self.max_chunk_size);
+            // filled + len <= dst.len(), so the range stays inside dst
+            let size = unsafe { self.read_once_raw(ptr.add(filled), len)? };
+            // read_once_raw rejects reads reporting more than requested
+            debug_assert!(size <= len);
+            filled += size;
+        }
+        debug_assert_eq!(filled, dst.len());
+
+        // all dst.len() bytes were written by reads above
+        Ok(unsafe { std::slice::from_raw_parts_mut(ptr, dst.len()) })
+    }
+
+    /// returns an array of `N` random bytes, with `N` usually inferred at the call site
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn try_fill_array<const N: usize>(&mut self) -> Result<[u8; N], std::io::Error> {
+        let mut bytes = [0u8; N];
+        self.try_fill_bytes(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// allocates and returns `n` random bytes, which are zeroized when dropped
+    ///
+    /// This protects key material against being left behind in freed heap memory, where it could
+    /// later be disclosed e.g. through a use-after-free, a core dump or memory reused by another
+    /// allocation. It does not protect against an attacker who can read process memory while the
+    /// bytes are alive, nor against copies the caller makes.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    #[cfg(feature = "zeroize")]
+    pub fn random_zeroizing_bytes(
+        &mut self,
+        n: usize,
+    ) -> Result<zeroize::Zeroizing<Vec<u8>>, std::io::Error> {
+        let mut bytes = zeroize::Zeroizing::new(vec![0u8; n]);
+        self.try_fill_bytes(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// fills `dst` with random bytes like [`TryRngCore::try_fill_bytes`], but gives up once
+    /// `deadline` has passed
+    ///
+    /// The clock is checked before every chunk, a single blocking read is not interrupted. Set a
+    /// read timeout to bound that as well.
+    ///
+    /// # Errors
+    /// Returns [`RandJitterError::DeadlineExceeded`] wrapped inside a `std::io::Error` of kind
+    /// `TimedOut` if the deadline passes before `dst` is full. The bytes before `filled` are
+    /// written, the rest of `dst` is left untouched. Otherwise the same as
+    /// [`TryRngCore::try_fill_bytes`].
+    pub fn try_fill_bytes_deadline(
+        &mut self,
+        dst: &mut [u8],
+        deadline: std::time::Instant,
+    ) -> Result<(), std::io::Error> {
+        let mut filled = 0;
+        while filled < dst.len() {
+            if std::time::Instant::now() >= deadline {
+                return Err(RandJitterError::DeadlineExceeded {
+                    filled,
+                    expected: dst.len(),
+                }
+                .into());
+            }
+            let chunk_size = (dst.len() - filled).min(self.max_chunk_size);
+            self.try_fill_bytes_max_chunk_size(&mut dst[filled..filled + chunk_size])?;
+            filled += chunk_size;
+        }
+
+        Ok(())
+    }
+
+    /// writes `n` random bytes to `writer`, one chunk at a time, e.g. to seed an external entropy
+    /// pool from a file or socket
+    ///
+    /// At most one chunk is held in memory, it is wiped before returning.
+    ///
+    /// # Errors
+    /// Returns [`RandJitterError::Write`] wrapped inside a `std::io::Error` if writing fails, so
+    /// write errors can be told apart from read errors, which are the same as for
+    /// [`TryRngCore::try_fill_bytes`].
+    pub fn write_to<W: std::io::Write>(
+        &mut self,
+        writer: &mut W,
+        n: usize,
+    ) -> Result<(), std::io::Error> {
+        let mut chunk = vec![0u8; n.min(self.max_chunk_size)];
+        let mut written = 0;
+        let result = loop {
+            if written == n {
+                break Ok(());
+            }
+            let len = (n - written).min(chunk.len());
+            if let Err(err) = self.try_fill_bytes_max_chunk_size(&mut chunk[..len]) {
+                break Err(err);
+            }
+            if let Err(err) = writer.write_all(&chunk[..len]) {
+                break Err(RandJitterError::Write(err));
+            }
+            written += len;
+        };
+        wipe(&mut chunk);
+
+        Ok(result?)
+    }
+
+    /// constructs an rng of type `R`, e.g. a fast PRNG, seeded from this instance
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn seed_rng<R: rand_core::SeedableRng>(&mut self) -> Result<R, std::io::Error> {
+        let mut seed = R::Seed::default();
+        self.try_fill_bytes(seed.as_mut())?;
+        Ok(R::from_seed(seed))
+    }
+
+    /// boxes this instance as a trait object, e.g. to store it next to other entropy sources
+    ///
+    /// The box implements [`TryRngCore`] again, see [`DynTryRng`] for why `dyn TryRngCore`
+    /// cannot be used directly.
+    #[must_use]
+    pub fn boxed(self) -> Box<dyn DynTryRng<Error = std::io::Error> + Send> {
+        Box::new(self)
+    }
+
+    /// returns an iterator yielding one random byte at a time, buffering one chunk internally
+    pub fn bytes_iter(&mut self) -> BytesIter<'_> {
+        BytesIter::new(self)
+    }
+
+    /// creates a new instance owning a duplicate of the rng descriptor
+    ///
+    /// # Errors
+    /// Returns the OS error if the descriptor cannot be duplicated.
+    pub fn try_clone(&self) -> Result<Self, std::io::Error> {
+        let cmd = if self.options.cloexec {
+            libc::F_DUPFD_CLOEXEC
+        } else {
+            libc::F_DUPFD
+        };
+        let min_fd = if self.options.avoid_stdio_fds {
+            MIN_NON_STDIO_FD
+        } else {
+            0
+        };
+        let buffer =
+            buffer::ReadBuffer::with_chunks(self.options.buffer_capacity, self.max_chunk_size)?;
+        let slot = limit::acquire()?;
+        let rng_fd = unsafe { libc::fcntl(self.rng_fd, cmd, min_fd) };
+        if rng_fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(RandJitterKernel {
+            rng_fd,
+            max_chunk_size: self.max_chunk_size,
+            options: self.options,
+            buffer,
+            bytes_generated: 0,
+            #[cfg(feature = "stats")]
+            latency: stats::LatencyStats::default(),
+            reader: None,
+            _slot: Some(slot),
+            #[cfg(all(feature = "io_uring", target_os = "linux"))]
+            uring: uring::LazyRing::default(),
+        })
+    }
+
+    /// replaces the rng descriptor with a freshly accepted instance, e.g. after it became invalid
+    ///
+    /// The current descriptor is closed if it is still valid. On error, the instance is left
+    /// unchanged.
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`]. Returns [`RandJitterError::InvalidFd`] for an instance
+    /// reading from an injected reader.
+    pub fn reopen(&mut self) -> Result<(), std::io::Error> {
+        Ok(self.reopen_fd(true)?)
+    }
+
+    /// enables or disables reopening the descriptor once if a read fails with `EBADF` or hits end
+    /// of file
+    ///
+    /// This is disabled by default, so a broken descriptor fails fast.
+    #[must_use]
+    pub fn with_auto_reopen(mut self, auto_reopen: bool) -> Self {
+        self.options.auto_reopen = auto_reopen;
+        self
+    }
+
+    /// sets a timeout for reads from the rng descriptor, `None` restores blocking indefinitely
+    ///
+    /// A read that does not complete in time fails with [`RandJitterError::TimedOut`] and
+    /// `std::io::ErrorKind::TimedOut`. Note that `algif_rng` generates the requested bytes
+    /// synchronously inside `read()`, so the timeout only covers time spent waiting on the socket.
+    ///
+    /// # Errors
+    /// Returns `std::io::ErrorKind::InvalidInput` for a zero timeout, or the OS error if
+    /// `SO_RCVTIMEO` cannot be set.
+    pub fn set_read_timeout(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<(), std::io::Error> {
+        set_fd_read_timeout(self.rng_fd, timeout)?;
+        self.options.read_timeout = timeout;
+        Ok(())
+    }
+
+    /// puts the rng descriptor into non-blocking mode or back into blocking mode
+    ///
+    /// In non-blocking mode, a read that cannot complete immediately fails with
+    /// `std::io::ErrorKind::WouldBlock`, so the descriptor can be registered with a reactor and
+    /// the read retried once it is readable. Bytes of a partially filled buffer are discarded.
+    ///
+    /// # Errors
+    /// Returns the OS error if `O_NONBLOCK` cannot be changed.
+    pub fn set_nonblocking(&mut self, nonblocking: bool) -> Result<(), std::io::Error> {
+        set_fd_nonblocking(self.rng_fd, nonblocking)?;
+        self.options.nonblocking = nonblocking;
+        Ok(())
+    }
+
+    /// replaces the descriptor, closing the old one only if `close_old` is set, which callers
+    /// must not do after the kernel rejected it with `EBADF`
+    fn reopen_fd(&mut self, close_old: bool) -> Result<(), RandJitterError> {
+        // an injected reader keeps serving reads, a socket opened next to it would only leak
+        if self.reader.is_some() {
+            return Err(RandJitterError::InvalidFd);
+        }
+        // the instance keeps its slot, so reopening works at the instance limit
+        let fresh = Self::open_with_slot(&self.options, None)?;
+        let rng_fd = std::os::fd::IntoRawFd::into_raw_fd(fresh);
+
+        // if the old descriptor was already closed, the kernel may have handed out its number again
+        if close_old && self.rng_fd >= 0 && self.rng_fd != rng_fd {
+            sys::close(self.rng_fd);
+        }
+        self.rng_fd = rng_fd;
+
+        Ok(())
+    }
+
+    /// fills all `bufs` with random bytes using `readv`, e.g. a key and a nonce at once
+    ///
+    /// Each `readv` call covers at most `max_chunk_size()` bytes across the slices.
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn try_fill_vectored(
+        &mut self,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+    ) -> Result<(), std::io::Error> {
+        Ok(self.fill_vectored(bufs)?)
+    }
+
+    fn fill_vectored(
+        &mut self,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+    ) -> Result<(), RandJitterError> {
+        // readv() needs a descriptor, fill injected readers one slice at a time
+        if self.reader.is_some() {
+            for buf in bufs.iter_mut() {
+                for chunk in buf.chunks_mut(self.max_chunk_size) {
+                    self.try_fill_bytes_max_chunk_size(chunk)?;
+                }
+            }
+            return Ok(());
+        }
+        if self.rng_fd < 0 {
+            return Err(RandJitterError::InvalidFd);
+        }
+
+        let total: usize = bufs.iter().map(|buf| buf.len()).sum();
+        let mut filled = 0;
+        let mut iovecs = Vec::with_capacity(bufs.len());
+        while filled < total {
+            // collect the unfilled parts of the slices, up to one chunk in total
+            iovecs.clear();
+            let mut skip = filled;
+            let mut budget = self.max_chunk_size;
+            for buf in bufs.iter_mut() {
+                if budget == 0 {
+                    break;
+                }
+                if skip >= buf.len() {
+                    skip -= buf.len();
+                    continue;
+                }
+
+                let rest = &mut buf[skip..];
+                let len = rest.len().min(budget);
+                iovecs.push(libc::iovec {
+                    iov_base: rest.as_mut_ptr().cast::<libc::c_void>(),
+                    iov_len: len,
+                });
+                budget -= len;
+                skip = 0;
+            }
+
+            // the iovecs point into bufs, which stay borrowed for the whole loop
+            let len = self.max_chunk_size - budget;
+            filled += unsafe { self.read_once_target(ReadTarget::Vectored(&iovecs), len)? };
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            bytes = total,
+            "filled vectored bytes from jitterentropy_rng"
+        );
+        Ok(())
+    }
+
+    /// number of bytes read from the kernel since this instance was created or
+    /// [`Self::reset_stats`] was last called
+    ///
+    /// This includes bytes still held in the internal buffer for small requests. The counter
+    /// saturates at `u64::MAX` instead of wrapping around.
+    #[must_use]
+    pub fn bytes_generated(&self) -> u64 {
+        self.bytes_generated
+    }
+
+    /// latency statistics of all successful reads of this instance, including reads that only
+    /// refill the internal buffer
+    #[cfg(feature = "stats")]
+    #[must_use]
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency
+    }
+
+    /// zeros the byte counter and, with the `stats` feature, the latency statistics
+    ///
+    /// Only resets the counters, the descriptor and the internal buffer are left alone. Useful
+    /// for metrics that report the bytes generated since the last scrape.
+    pub fn reset_stats(&mut self) {
+        self.bytes_generated = 0;
+        #[cfg(feature = "stats")]
+        {
+            self.latency = stats::LatencyStats::default();
+        }
+    }
+
+    fn count_bytes(&mut self, n: usize) {
+        let n = u64::try_from(n).unwrap_or(u64::MAX);
+        self.bytes_generated = self.bytes_generated.saturating_add(n);
+    }
+
+    /// drops and wipes all bytes buffered for small requests like `try_next_u64`
+    pub fn clear_buffer(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// serves small requests from the internal buffer, refilling it with a single read when empty
+    fn fill_buffered(&mut self, dst: &mut [u8]) -> Result<(), RandJitterError> {
+        if self.buffer.capacity() == 0 {
+            return self.try_fill_bytes_max_chunk_size(dst);
+        }
+
+        let mut filled = self.buffer.take(dst);
+        while filled < dst.len() {
+            // move the buffer out, so read_chunks can borrow self
+            let mut buffer = std::mem::take(&mut self.buffer);
+            let refilled = buffer.refill(|data| self.read_chunks(data));
+            self.buffer = buffer;
+
+            if refilled? == 0 {
+                return Err(RandJitterError::ShortRead {
+                    got: filled,
+                    expected: dst.len(),
+                });
+            }
+            filled += self.buffer.take(&mut dst[filled..]);
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(bytes = dst.len(), "filled bytes from buffer");
+        Ok(())
+    }
+
+    /// reads chunk by chunk into `dst` until it is full or a read comes back short, returns the
+    /// number of bytes read
+    fn read_chunks(&mut self, dst: &mut [u8]) -> Result<usize, RandJitterError> {
+        let mut filled = 0;
+        while filled < dst.len() {
+            let len = (dst.len() - filled).min(self.max_chunk_size);
+            let size = match self.read_once(&mut dst[filled..filled + len]) {
+                Ok(size) => size,
+                // hand out what was read, the error shows up again on the next read
+                Err(_) if filled > 0 => break,
+                Err(err) => return Err(err),
+            };
+            filled += size;
+            if size < len {
+                break;
+            }
+        }
+        Ok(filled)
+    }
+
+    fn try_fill_bytes_max_chunk_size(&mut self, dst: &mut [u8]) -> Result<(), RandJitterError> {
+        if dst.len() > self.max_chunk_size {
+            return Err(RandJitterError::ChunkTooLarge {
+                requested: dst.len(),
+                max: self.max_chunk_size,
+            });
+        }
+
+        // a read of the full chunk completes it, a short read is legal and continues reading,
+        // read_once already rejects reads beyond the chunk
+        let mut filled = 0;
+        while filled < dst.len() {
+            let size = self.read_once(&mut dst[filled..])?;
+            filled += size;
+            #[cfg(feature = "log")]
+            if filled < dst.len() {
+                log::warn!(
+                    "read() returned {size} byte, {filled} of {} byte filled, retrying",
+                    dst.len()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// issues a single successful `read()` and returns its size, which is only 0 for an empty
+    /// `dst`, end of file is reported as [`RandJitterError::SourceClosed`]
+    fn read_once(&mut self, dst: &mut [u8]) -> Result<usize, RandJitterError> {
+        // an initialized slice is valid for writes of its length
+        unsafe { self.read_once_raw(dst.as_mut_ptr(), dst.len()) }
+    }
+
+    /// like [`Self::read_once`], but reads into `len` bytes at `dst`
+    ///
+    /// # Safety
+    /// `dst` must be valid for writes of `len` bytes. If a reader is injected, the bytes must
+    /// also be initialized, as the reader gets them as a slice.
+    unsafe fn read_once_raw(&mut self, dst: *mut u8, len: usize) -> Result<usize, RandJitterError> {
+        unsafe { self.read_once_target(ReadTarget::Buf(dst), len) }
+    }
+
+    /// issues a single `read()` or `readv()` of `len` bytes into `target` and handles its
+    /// errors: retrying `EINTR`, reporting timeouts, reopening after end of file or `EBADF` and
+    /// rejecting reads beyond `len`
+    ///
+    /// # Safety
+    /// `target` must be valid for writes of `len` bytes, see [`Self::read_once_raw`]. The
+    /// iovecs of a vectored target must cover exactly `len` bytes.
+    unsafe fn read_once_target(
+        &mut self,
+        target: ReadTarget<'_>,
+        len: usize,
+    ) -> Result<usize, RandJitterError> {
+        #[cfg(feature = "log")]
+        let syscall = match target {
+            ReadTarget::Buf(_) => "read()",
+            ReadTarget::Vectored(_) => "readv()",
+        };
+        if self.rng_fd < 0 && self.reader.is_none() {
+            return Err(RandJitterError::InvalidFd);
+        }
+
+        // the span covers all retries, so slow reads show up in its duration
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("read", len).entered();
+
+        #[cfg(feature = "stats")]
+        let start = std::time::Instant::now();
+
+        let mut interrupts = 0;
+        let mut reopened = false;
+        loop {
+            // the caller guarantees target to be valid for len bytes, initialized if a reader is
+            // set, and the descriptor is read again as reopening replaces it
+            let result = match (target, &mut self.reader) {
+                (ReadTarget::Buf(dst), Some(reader)) => {
+                    reader.read(unsafe { std::slice::from_raw_parts_mut(dst, len) })
+                }
+                (ReadTarget::Buf(dst), None) => unsafe {
+                    reader::read_fd_raw(self.rng_fd, dst, len)
+                },
+                (ReadTarget::Vectored(iovecs), _) => unsafe { sys::readv(self.rng_fd, iovecs) },
+            };
+            let err = match result {
+                // read() never returns more than requested, so this is a bug in the reader
+                Ok(size) if size > len => {
+                    #[cfg(feature = "log")]
+                    log::error!("{syscall} returned {size} byte, more than the {len} requested");
+                    return Err(RandJitterError::OverRead {
+                        got: size,
+                        requested: len,
+                    });
+                }
+                // read() only returns 0 for a non-empty buffer at end of file, e.g. after the
+                // kernel or the peer shut down the socket
+                Ok(0) if len > 0 => {
+                    if self.options.auto_reopen && self.reader.is_none() && !reopened {
+                        #[cfg(feature = "log")]
+                        log::warn!("{syscall} hit end of file, reopening");
+                        self.reopen_fd(true)?;
+                        reopened = true;
+                        continue;
+                    }
+                    #[cfg(feature = "log")]
+                    log::error!("{syscall} hit end of file");
+                    return Err(RandJitterError::SourceClosed);
+                }
+                Ok(size) => {
+                    #[cfg(feature = "stats")]
+                    self.latency.record(start.elapsed());
+                    self.count_bytes(size);
+                    return Ok(size);
+                }
+                Err(err) => err,
+            };
+
+            if err.kind() == std::io::ErrorKind::Interrupted && interrupts < MAX_EINTR_RETRIES {
+                interrupts += 1;
+                #[cfg(feature = "log")]
+                log::warn!("{syscall} interrupted with errno {}, retrying", libc::EINTR);
+                continue;
+            }
+            // SO_RCVTIMEO reports an expired timeout as EAGAIN, in non-blocking mode EAGAIN is
+            // passed on as WouldBlock
+            if err.kind() == std::io::ErrorKind::WouldBlock
+                && self.options.read_timeout.is_some()
+                && !self.options.nonblocking
+            {
+                #[cfg(feature = "log")]
+                log::error!("{syscall} timed out with errno {}", libc::EAGAIN);
+                return Err(RandJitterError::TimedOut);
+            }
+            if err.raw_os_error() == Some(libc::EBADF)
+                && self.options.auto_reopen
+                && self.reader.is_none()
+                && !reopened
+            {
+                #[cfg(feature = "log")]
+                log::warn!("{syscall} failed with errno {}, reopening", libc::EBADF);
+                // the number is no longer ours, another thread may have been handed it already
+                self.reopen_fd(false)?;
+                reopened = true;
+                continue;
+            }
+            #[cfg(feature = "log")]
+            log_read_failure(syscall, &err);
+            return Err(RandJitterError::Read(err));
+        }
+    }
+}
+
+/// destination of a single read of [`RandJitterKernel::read_once_target`]
+#[derive(Clone, Copy)]
+enum ReadTarget<'a> {
+    /// contiguous bytes, read with `read()` or an injected reader
+    Buf(*mut u8),
+    /// slices described by iovecs, read with `readv()`
+    Vectored(&'a [libc::iovec]),
+}
+
+/// logs a failed read unless it only reports that a non-blocking descriptor has no data yet
+#[cfg(feature = "log")]
+fn log_read_failure(syscall: &str, err: &std::io::Error) {
+    if err.kind() != std::io::ErrorKind::WouldBlock {
+        log::error!(
+            "{syscall} failed with errno {}: {err}",
+            err.raw_os_error().unwrap_or_default()
+        );
+    }
+}
+
+/// returns whether an `AF_ALG` socket can be bound to `jitterentropy_rng` on this host
+///
+/// Only the `socket()` and `bind()` steps are probed and the socket is closed again, so some
+/// hosts may still fail later in [`RandJitterKernel::new`], e.g. when running out of descriptors.
+#[must_use]
+pub fn is_available() -> bool {
+    match open_family_fd(
+        true,
+        &algorithm::AlgorithmName::default(),
+        SocketType::default(),
+    ) {
+        Ok(fam_fd) => {
+            sys::close(fam_fd);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// accepts a new rng descriptor on the bound family socket `fam_fd`
+#[cfg(target_os = "linux")]
+fn accept_rng_fd(fam_fd: libc::c_int, cloexec: bool) -> Result<libc::c_int, RandJitterError> {
+    let flags = if cloexec { libc::SOCK_CLOEXEC } else { 0 };
+    sys::accept4(fam_fd, flags).map_err(|err| fd_limit_or(err, RandJitterError::Accept))
+}
+
+/// reports running out of descriptors (`EMFILE`, `ENFILE`) distinctly, other errors via `other`
+#[cfg(target_os = "linux")]
+fn fd_limit_or(
+    err: std::io::Error,
+    other: fn(std::io::Error) -> RandJitterError,
+) -> RandJitterError {
+    match err.raw_os_error() {
+        Some(libc::EMFILE | libc::ENFILE) => RandJitterError::FdLimit(err),
+        _ => other(err),
+    }
+}
+
+/// reports a sandbox denying `socket()` and running out of descriptors distinctly
+#[cfg(target_os = "linux")]
+fn socket_error(err: std::io::Error) -> RandJitterError {
+    match err.raw_os_error() {
+        Some(libc::EPERM | libc::EACCES) => RandJitterError::PermissionDenied(err),
+        _ => fd_limit_or(err, RandJitterError::SocketCreate),
+    }
+}
+
+/// `AF_ALG` with `jitterentropy_rng` is currently only implemented inside the Linux kernel
+#[cfg(not(target_os = "linux"))]
+fn accept_rng_fd(_fam_fd: libc::c_int, _cloexec: bool) -> Result<libc::c_int, RandJitterError> {
+    Err(RandJitterError::Unsupported)
+}
+
+/// `AF_ALG` with `jitterentropy_rng` is currently only implemented inside the Linux kernel
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn open_family_fd(
+    _cloexec: bool,
+    _algorithm: &algorithm::AlgorithmName,
+    _socket_type: SocketType,
+) -> Result<libc::c_int, RandJitterError> {
+    Err(RandJitterError::Unsupported)
+}
+
+/// creates an `AF_ALG` socket of `socket_type` bound to the rng `algorithm`, the caller has to close it
+#[cfg(target_os = "linux")]
+pub(crate) fn open_family_fd(
+    cloexec: bool,
+    algorithm: &algorithm::AlgorithmName,
+    socket_type: SocketType,
+) -> Result<libc::c_int, RandJitterError> {
+    let mut sock_addr: sys::SockaddrAlg = unsafe { std::mem::zeroed() };
+    sock_addr.salg_family = u16::try_from(libc::AF_ALG).map_err(|_| {
+        RandJitterError::Bind(std::io::Error::from(std::io::ErrorKind::InvalidInput))
+    })?;
+    let rng_type = "rng";
+    let rng_name = algorithm.as_bytes();
+
+    sock_addr.salg_type[..rng_type.len()].copy_from_slice(rng_type.to_string().as_bytes());
+    sock_addr.salg_name[..rng_name.len()].copy_from_slice(rng_name);
+
+    // close this on every (early) return!
+    // by default both fds are close-on-exec, so they do not leak into exec'ed child processes
+    let flags = if cloexec { libc::SOCK_CLOEXEC } else { 0 };
+    let fam_fd =
+        sys::socket(libc::AF_ALG, socket_type.as_raw() | flags, 0).map_err(socket_error)?;
+
+    if let Err(err) = sys::bind(fam_fd, &sock_addr) {
+        sys::close(fam_fd);
+        // the kernel reports unknown algorithms with ENOENT
+        if err.raw_os_error() == Some(libc::ENOENT) {
+            return Err(RandJitterError::NotRegistered(err));
+        }
+        return Err(RandJitterError::Bind(err));
+    }
+
+    Ok(fam_fd)
+}
+
+/// moves `fd` to the lowest free descriptor number of at least `min_fd`, closing the original
+///
+/// `fd` is returned unchanged if it is already at or above `min_fd`. On error, `fd` is closed.
+fn move_fd_above(
+    fd: libc::c_int,
+    min_fd: libc::c_int,
+    cloexec: bool,
+) -> Result<libc::c_int, std::io::Error> {
+    if fd >= min_fd {
+        return Ok(fd);
+    }
+    let cmd = if cloexec {
+        libc::F_DUPFD_CLOEXEC
+    } else {
+        libc::F_DUPFD
+    };
+    let moved = unsafe { libc::fcntl(fd, cmd, min_fd) };
+    // fetch errno before close() can overwrite it
+    let result = if moved < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(moved)
+    };
+    sys::close(fd);
+    result
+}
+
+/// sets or clears `O_NONBLOCK` on `fd`
+fn set_fd_nonblocking(fd: libc::c_int, nonblocking: bool) -> Result<(), std::io::Error> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let flags = if nonblocking {
+        flags | libc::O_NONBLOCK
+    } else {
+        flags & !libc::O_NONBLOCK
+    };
+    if unsafe { libc::fcntl(fd, libc::F_SETFL, flags) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// sets `SO_RCVTIMEO` on `fd`, `None` blocks indefinitely
+fn set_fd_read_timeout(
+    fd: libc::c_int,
+    timeout: Option<std::time::Duration>,
+) -> Result<(), std::io::Error> {
+    let timeval = match timeout {
+        // like std, reject a zero timeout, the kernel would treat it as blocking indefinitely
+        Some(timeout) if timeout.is_zero() => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "cannot set a zero duration timeout",
+            ));
+        }
+        Some(timeout) => {
+            let mut timeval = libc::timeval {
+                tv_sec: libc::time_t::try_from(timeout.as_secs()).unwrap_or(libc::time_t::MAX),
+                // below 10^6, so it fits into the suseconds_t of every target
+                tv_usec: i32::try_from(timeout.subsec_micros()).map_or(0, libc::suseconds_t::from),
+            };
+            // round timeouts below one microsecond up instead of blocking indefinitely
+            if timeval.tv_sec == 0 && timeval.tv_usec == 0 {
+                timeval.tv_usec = 1;
+            }
+            timeval
+        }
+        None => libc::timeval {
+            tv_sec: 0,
+            tv_usec: 0,
+        },
+    };
+
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            std::ptr::addr_of!(timeval).cast::<libc::c_void>(),
+            u32::try_from(std::mem::size_of_val(&timeval))
+                .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?,
+        )
+    };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// overwrites a temporary buffer with zeros, so no entropy residue is left behind on the stack
+///
+/// Volatile writes keep the compiler from optimizing the wipe of a buffer that is not read anymore away.
+fn wipe(buf: &mut [u8]) {
+    for byte in buf.iter_mut() {
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+impl PartialEq for RandJitterKernel {
+    fn eq(&self, other: &Self) -> bool {
+        self.rng_fd == other.rng_fd
+    }
+}
+
+impl Eq for RandJitterKernel {}
+
+impl PartialOrd for RandJitterKernel {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RandJitterKernel {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.rng_fd.cmp(&other.rng_fd)
+    }
+}
+
+impl Default for RandJitterKernel {
+    /// constructs new RNG instance for convenience
+    ///
+    /// # Panics
+    /// Panics if the instance cannot be constructed, e.g. when `AF_ALG` is blocked inside a
+    /// container or sandbox. Use [`RandJitterKernel::new`] or [`RandJitterKernel::try_default`]
+    /// to handle this case.
+    fn default() -> Self {
+        Self::try_default().expect("unable to construct RandJitterKernel")
+    }
+}
+
+impl Clone for RandJitterKernel {
+    /// duplicates the rng descriptor, each clone closes its own descriptor on drop
+    ///
+    /// # Panics
+    /// Panics if the descriptor cannot be duplicated, use [`RandJitterKernel::try_clone`]
+    /// to handle this case.
+    fn clone(&self) -> Self {
+        self.try_clone()
+            .expect("unable to duplicate rng_fd of RandJitterKernel")
+    }
+}
+
+impl std::fmt::Debug for RandJitterKernel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RandJitterKernel")
+            .field("open", &self.is_open())
+            .finish()
+    }
+}
+
+impl std::fmt::Display for RandJitterKernel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = if self.rng_fd >= 0 {
+            "open"
+        } else if self.reader.is_some() {
+            "reader"
+        } else {
+            "closed"
+        };
+        write!(
+            f,
+            "{}(fd={state}, generated={} bytes)",
+            self.options.algorithm.as_str(),
+            self.bytes_generated
+        )
+    }
+}
+
+impl Drop for RandJitterKernel {
+    fn drop(&mut self) {
+        // never panic here, a panic while unwinding aborts the process
+        if self.rng_fd >= 0 {
+            sys::close(self.rng_fd);
+        }
+        self.rng_fd = -1;
+    }
+}
+
+/// The descriptor stays owned by the instance and is closed on drop,
+/// so it must not be closed externally.
+impl std::os::fd::AsRawFd for RandJitterKernel {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.rng_fd
+    }
+}
+
+/// # Panics
+/// Panics if the instance holds no descriptor, e.g. when it reads from an injected reader, as
+/// `BorrowedFd` cannot represent `-1`.
+impl std::os::fd::AsFd for RandJitterKernel {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        assert!(
+            self.rng_fd >= 0,
+            "RandJitterKernel holds no descriptor to borrow, it is closed or reads from an injected reader"
+        );
+        // a non-negative rng_fd stays open until the instance is dropped or consumed
+        unsafe { std::os::fd::BorrowedFd::borrow_raw(self.rng_fd) }
+    }
+}
+
+impl std::os::fd::FromRawFd for RandJitterKernel {
+    /// wraps an already accepted `AF_ALG` rng descriptor, e.g. one received via `SCM_RIGHTS`
+    ///
+    /// # Safety
+    /// `fd` must be an open descriptor owned by the caller. Ownership is transferred to the
+    /// returned instance, which closes it on drop.
+    unsafe fn from_raw_fd(fd: std::os::fd::RawFd) -> Self {
+        RandJitterKernel {
+            rng_fd: fd,
+            max_chunk_size: MAX_RETURN_CHUNK_SIZE,
+            options: builder::Options::default(),
+            buffer: buffer::ReadBuffer::new(),
+            bytes_generated: 0,
+            #[cfg(feature = "stats")]
+            latency: stats::LatencyStats::default(),
+            reader: None,
+            _slot: None,
+            #[cfg(all(feature = "io_uring", target_os = "linux"))]
+            uring: uring::LazyRing::default(),
+        }
+    }
+}
+
+impl RandJitterKernel {
+    /// closes the descriptor and reports a failing `close()`, which dropping the instance ignores
+    ///
+    /// Instances backed by an [`EntropyReader`] have no descriptor and always succeed.
+    ///
+    /// # Errors
+    /// Returns [`RandJitterError::InvalidFd`] if the descriptor is already closed, and
+    /// [`RandJitterError::Close`] if `close()` fails. The descriptor must not be used again in
+    /// either case, as Linux releases it even if `close()` reports an error.
+    pub fn close(mut self) -> Result<(), std::io::Error> {
+        let rng_fd = std::mem::replace(&mut self.rng_fd, -1);
+        if rng_fd < 0 {
+            if self.reader.is_some() {
+                return Ok(());
+            }
+            return Err(RandJitterError::InvalidFd.into());
+        }
+        sys::close_checked(rng_fd).map_err(|err| RandJitterError::Close(err).into())
+    }
+}
+
+impl std::os::fd::IntoRawFd for RandJitterKernel {
+    /// consumes the instance without closing the descriptor
+    fn into_raw_fd(mut self) -> std::os::fd::RawFd {
+        // drop skips close() for -1, but still wipes and frees the buffer
+        std::mem::replace(&mut self.rng_fd, -1)
+    }
+}
+
+/// Every call to `read` returns at most one chunk of `max_chunk_size()` bytes.
+/// Use `TryRngCore::try_fill_bytes` to fill a larger buffer completely.
+impl std::io::Read for RandJitterKernel {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.max_chunk_size);
+        self.try_fill_bytes_max_chunk_size(&mut buf[..len])?;
+        Ok(len)
+    }
+}
+
+impl TryRngCore for RandJitterKernel {
+    type Error = std::io::Error;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        // only takes 4 bytes, half the entropy of try_next_u64
+        self.next_from_bytes(u32::from_ne_bytes)
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        self.next_from_bytes(u64::from_ne_bytes)
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        let mut idx = 0;
+        // batch large fills into few io_uring submissions, read() fills whatever is left and
+        // handles errors. io_uring ignores SO_RCVTIMEO and O_NONBLOCK, so those use read() only.
+        #[cfg(all(feature = "io_uring", target_os = "linux"))]
+        if self.reader.is_none()
+            && self.options.read_timeout.is_none()
+            && !self.options.nonblocking
+            && dst.len() >= 2 * self.max_chunk_size
+        {
+            #[cfg(feature = "stats")]
+            let start = std::time::Instant::now();
+            idx = self.uring.fill(self.rng_fd, dst, self.max_chunk_size);
+            // a batched fill is recorded as one read
+            #[cfg(feature = "stats")]
+            if idx > 0 {
+                self.latency.record(start.elapsed());
+            }
+            self.count_bytes(idx);
+        }
+        // slices never exceed isize::MAX bytes, so idx + chunk_size below cannot overflow, the
+        // saturating add only guards the comparison against a huge max_chunk_size
+        debug_assert!(dst.len() <= isize::MAX.unsigned_abs());
+        while idx < dst.len() {
+            let end = idx.saturating_add(self.max_chunk_size).min(dst.len());
+            self.try_fill_bytes_max_chunk_size(&mut dst[idx..end])?;
+            idx = end;
+        }
+        assert_eq!(idx, dst.len());
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(bytes = dst.len(), "filled bytes from jitterentropy_rng");
+        Ok(())
+    }
+}
+
+/// `jitterentropy_rng` is an SP800-90B entropy source intended for cryptographic use
+impl TryCryptoRng for RandJitterKernel {}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{pipe, pipe_rng, send_packet, seqpacket_pair};
+    use crate::{
+        MAX_RETURN_CHUNK_SIZE, MIN_NON_STDIO_FD, PROBE_READ_SIZE, RandJitterError,
+        RandJitterKernel, is_available, move_fd_above,
+    };
+    use rand_core::TryRngCore;
+
+    #[test]
+    fn test_u32() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        for _ in 0..1000 {
+            let u = rng.try_next_u32();
+            assert!(u.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_u64() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        for _ in 0..1000 {
+            let u = rng.try_next_u64();
+            assert!(u.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_speed() {
+        use std::time::Instant;
+        let start = Instant::now();
+        let mut num_bytes = 0usize;
+        let mut rng = RandJitterKernel::new().unwrap();
+
+        loop {
+            let mut b = [0u8; 32];
+            rng.try_fill_bytes(&mut b).unwrap();
+
+            let now = Instant::now();
+
+            num_bytes += b.len();
+
+            if (now - start).as_secs() > 2 {
+                let datarate = f64::from(u32::try_from(num_bytes).unwrap())
+                    / (now - start).as_secs_f64()
+                    / 1024.0;
+                println!("datarate: {datarate} KiB/s");
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_bytes() {
+        let mut rng = RandJitterKernel::new().unwrap();
+
+        for buffer_size in 0..=256 {
+            let mut buffer = vec![0u8; buffer_size];
+            assert!(rng.try_fill_bytes(&mut buffer).is_ok());
+            println!("{buffer_size}: {buffer:#04X?}");
+        }
+    }
+
+    #[test]
+    fn test_large_bytes_but_ok() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        let mut buffer = [0u8; 128];
+        assert!(rng.try_fill_bytes_max_chunk_size(&mut buffer).is_ok());
+    }
+
+    #[test]
+    fn test_too_large_bytes() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        let mut buffer = [0u8; 129];
+        assert!(matches!(
+            rng.try_fill_bytes_max_chunk_size(&mut buffer),
+            Err(RandJitterError::ChunkTooLarge {
+                requested: 129,
+                max: MAX_RETURN_CHUNK_SIZE
+            })
+        ));
+    }
+
+    #[test]
+    fn test_chunk_too_large_error() {
+        use std::os::fd::FromRawFd;
+
+        // the length is checked before the descriptor is used
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(-1) };
+        rng.max_chunk_size = 64;
+        let err = rng
+            .try_fill_bytes_max_chunk_size(&mut [0u8; 65])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RandJitterError::ChunkTooLarge {
+                requested: 65,
+                max: 64
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Cannot return more than the maximum chunk size of 64 byte in a single call. Requested: 65 byte"
+        );
+    }
+
+    #[test]
+    fn test_error_into_io_error() {
+        let err: std::io::Error = RandJitterError::ShortRead {
+            got: 3,
+            expected: 8,
+        }
+        .into();
+        let inner = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<RandJitterError>())
+            .unwrap();
+        assert!(matches!(
+            inner,
+            RandJitterError::ShortRead {
+                got: 3,
+                expected: 8
+            }
+        ));
+    }
+
+    #[test]
+    fn test_error_keeps_errno() {
+        let err = RandJitterError::Bind(std::io::Error::from_raw_os_error(libc::ENOENT));
+        assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+        assert!(std::error::Error::source(&err).is_some());
+
+        let err: std::io::Error = err.into();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_not_registered_message() {
+        let err = RandJitterError::NotRegistered(std::io::Error::from_raw_os_error(libc::ENOENT));
+        assert!(err.to_string().contains("modprobe jitterentropy_rng"));
+        assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+    }
+
+    #[test]
+    fn test_try_default() {
+        let mut rng = RandJitterKernel::try_default().unwrap();
+        assert!(rng.try_next_u64().is_ok());
+    }
+
+    #[test]
+    fn test_cloexec() {
+        let rng = RandJitterKernel::new().unwrap();
+        let flags = unsafe { libc::fcntl(rng.rng_fd, libc::F_GETFD) };
+        assert!(flags >= 0);
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+    }
+
+    #[test]
+    fn test_as_fd() {
+        use std::os::fd::{AsFd, AsRawFd};
+
+        let rng = RandJitterKernel::new().unwrap();
+        assert_eq!(rng.as_raw_fd(), rng.rng_fd);
+        assert_eq!(rng.as_fd().as_raw_fd(), rng.rng_fd);
+    }
+
+    #[test]
+    #[should_panic(expected = "RandJitterKernel holds no descriptor to borrow")]
+    fn test_as_fd_without_descriptor() {
+        use std::os::fd::AsFd;
+
+        let rng = scripted(vec![]);
+        let _ = rng.as_fd();
+    }
+
+    /// reader returning scripted results, one per call
+    struct ScriptedReader(std::collections::VecDeque<std::io::Result<Vec<u8>>>);
+
+    impl crate::reader::EntropyReader for ScriptedReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.pop_front() {
+                Some(Ok(data)) => {
+                    let len = data.len().min(buf.len());
+                    buf[..len].copy_from_slice(&data[..len]);
+                    Ok(len)
+                }
+                Some(Err(err)) => Err(err),
+                None => Ok(0),
+            }
+        }
+    }
+
+    fn scripted(script: Vec<std::io::Result<Vec<u8>>>) -> RandJitterKernel {
+        RandJitterKernel::with_reader(Box::new(ScriptedReader(script.into())))
+    }
+
+    #[test]
+    fn test_from_raw_fd() {
+        let data = [0xA5u8; 8];
+        let (mut rng, _write_fd) = pipe_rng(&data);
+        assert_eq!(rng.try_next_u64().unwrap(), u64::from_ne_bytes(data));
+    }
+
+    #[test]
+    fn test_into_raw_fd() {
+        use std::os::fd::{FromRawFd, IntoRawFd};
+
+        let (read_fd, write_fd) = pipe();
+        let rng = unsafe { RandJitterKernel::from_raw_fd(read_fd) };
+        let fd = rng.into_raw_fd();
+        assert_eq!(fd, read_fd);
+
+        // still open after the instance is gone
+        assert!(unsafe { libc::fcntl(fd, libc::F_GETFD) } >= 0);
+
+        unsafe {
+            libc::close(fd);
+            libc::close(write_fd);
+        }
+    }
+
+    #[test]
+    fn test_fill_bytes_bulk() {
+        use std::os::fd::FromRawFd;
+
+        // a seqpacket read discards the rest of a longer packet, so send one packet per chunk
+        let packets = [vec![0x11; 128], vec![0x22; 128], vec![0x33; 44]];
+        let fill = |bulk: bool| {
+            let (rng_fd, peer_fd) = seqpacket_pair();
+            for packet in &packets {
+                send_packet(peer_fd, packet);
+            }
+            let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+            let mut buf = [0u8; 300];
+            if bulk {
+                rng.try_fill_bytes_bulk(&mut buf).unwrap();
+            } else {
+                rng.try_fill_bytes(&mut buf).unwrap();
+            }
+            unsafe { libc::close(peer_fd) };
+            buf
+        };
+        let bulk = fill(true);
+        assert_eq!(bulk, fill(false));
+        assert_eq!(bulk[..], packets.concat()[..]);
+
+        // a message above the chunk size arrives in a single read
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        send_packet(peer_fd, &[0x42; 200]);
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        let mut buf = [0u8; 200];
+        rng.try_fill_bytes_bulk(&mut buf).unwrap();
+        assert_eq!(buf, [0x42; 200]);
+        unsafe { libc::close(peer_fd) };
+        assert!(rng.try_fill_bytes_bulk(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_short_reads_are_completed() {
+        use std::os::fd::FromRawFd;
+
+        // every packet is returned by its own read(), so the chunk arrives in two pieces
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        send_packet(peer_fd, &[1, 2, 3]);
+        send_packet(peer_fd, &[4, 5, 6, 7, 8]);
+
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        let mut buffer = [0u8; 8];
+        rng.try_fill_bytes_max_chunk_size(&mut buffer).unwrap();
+        assert_eq!(buffer, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        unsafe { libc::close(peer_fd) };
+    }
+
+    #[test]
+    fn test_eof_is_source_closed() {
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        send_packet(peer_fd, &[1, 2, 3]);
+        unsafe { libc::close(peer_fd) };
+
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        let mut buffer = [0u8; 8];
+        assert!(matches!(
+            rng.try_fill_bytes_max_chunk_size(&mut buffer),
+            Err(RandJitterError::SourceClosed)
+        ));
+        assert_eq!(buffer[..3], [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_read_some() {
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        send_packet(peer_fd, &[1, 2, 3]);
+        send_packet(peer_fd, &[0x42; 200]);
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+
+        let mut buf = [0u8; 16];
+        assert_eq!(rng.try_read_some(&mut buf).unwrap(), 3);
+        assert_eq!(buf[..3], [1, 2, 3]);
+        let mut buf = [0u8; 256];
+        assert_eq!(rng.try_read_some(&mut buf).unwrap(), MAX_RETURN_CHUNK_SIZE);
+        assert_eq!(rng.try_read_some(&mut []).unwrap(), 0);
+        unsafe { libc::close(peer_fd) };
+        assert!(rng.try_read_some(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_fill_bytes_spin() {
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        rng.set_nonblocking(true).unwrap();
+
+        let mut buf = [0u8; 256];
+        let err = rng.try_fill_bytes_spin(&mut buf, 8).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+        // the data arrives while spinning, split into packets smaller than a chunk
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            for packet in 0..4u8 {
+                send_packet(peer_fd, &[packet; 64]);
+                std::thread::sleep(std::time::Duration::from_millis(2));
+            }
+            peer_fd
+        });
+        rng.try_fill_bytes_spin(&mut buf, 10_000).unwrap();
+        for (packet, chunk) in (0..4u8).zip(buf.chunks(64)) {
+            assert!(chunk.iter().all(|&b| b == packet));
+        }
+
+        unsafe { libc::close(sender.join().unwrap()) };
+    }
+
+    #[test]
+    fn test_eof_reopens() {
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        unsafe { libc::close(peer_fd) };
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        rng.options.auto_reopen = true;
+
+        // either the reopened kernel instance delivers, or opening it fails on this host
+        match rng.try_fill_bytes_max_chunk_size(&mut [0u8; 8]) {
+            Ok(()) => assert_ne!(rng.rng_fd, rng_fd),
+            Err(err) => assert!(!matches!(err, RandJitterError::SourceClosed)),
+        }
+    }
+
+    #[test]
+    fn test_retry_on_eintr() {
+        use std::os::fd::FromRawFd;
+        use std::os::unix::thread::JoinHandleExt;
+
+        extern "C" fn noop(_: libc::c_int) {}
+
+        // no SA_RESTART, so a blocking read() returns EINTR when the signal arrives
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = noop as *const () as libc::sighandler_t;
+            assert_eq!(
+                libc::sigaction(libc::SIGUSR1, &raw const action, std::ptr::null_mut()),
+                0
+            );
+        }
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        let reader = std::thread::spawn(move || {
+            let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+            let mut buffer = [0u8; 4];
+            rng.try_fill_bytes_max_chunk_size(&mut buffer)
+                .map(|()| buffer)
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        unsafe { libc::pthread_kill(reader.as_pthread_t(), libc::SIGUSR1) };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        send_packet(peer_fd, &[1, 2, 3, 4]);
+
+        assert_eq!(reader.join().unwrap().unwrap(), [1, 2, 3, 4]);
+        unsafe { libc::close(peer_fd) };
+    }
+
+    #[test]
+    fn test_io_read() {
+        use std::io::Read;
+
+        let mut rng = RandJitterKernel::new().unwrap();
+        let mut data = vec![];
+        std::io::copy(&mut (&mut rng).take(1000), &mut data).unwrap();
+        assert_eq!(data.len(), 1000);
+    }
+
+    #[test]
+    fn test_io_read_returns_one_chunk() {
+        use std::io::Read;
+
+        let data = [0x5Au8; 300];
+        let (mut rng, _write_fd) = pipe_rng(&data);
+        let mut buffer = [0u8; 300];
+        assert_eq!(rng.read(&mut buffer).unwrap(), MAX_RETURN_CHUNK_SIZE);
+        assert_eq!(rng.read(&mut []).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_max_chunk_size() {
+        let rng = RandJitterKernel::new().unwrap();
+        assert_eq!(rng.max_chunk_size(), MAX_RETURN_CHUNK_SIZE);
+        assert_eq!(RandJitterKernel::MAX_CHUNK_SIZE, MAX_RETURN_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_probe_max_read() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        let size = rng.probe_max_read().unwrap();
+        assert!(size > 0);
+        assert!(size <= PROBE_READ_SIZE);
+        assert_eq!(size, rng.max_chunk_size());
+    }
+
+    #[test]
+    fn test_probe_max_read_single_read() {
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        send_packet(peer_fd, &[0x42; 64]);
+        send_packet(peer_fd, &[0x42; 300]);
+        send_packet(peer_fd, &[0x17; 5000]);
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        // a read returning early never lowers the limit
+        assert_eq!(rng.probe_max_read().unwrap(), 64);
+        assert_eq!(rng.max_chunk_size(), MAX_RETURN_CHUNK_SIZE);
+        assert_eq!(rng.probe_max_read().unwrap(), 300);
+        assert_eq!(rng.max_chunk_size(), 300);
+        // a longer packet is cut at the probe size
+        assert_eq!(rng.probe_max_read().unwrap(), PROBE_READ_SIZE);
+        assert_eq!(rng.max_chunk_size(), PROBE_READ_SIZE);
+        assert_eq!(rng.bytes_generated(), 64 + 300 + PROBE_READ_SIZE as u64);
+        unsafe { libc::close(peer_fd) };
+    }
+
+    #[test]
+    fn test_random_bytes() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        assert_eq!(rng.random_bytes(300).unwrap().len(), 300);
+    }
+
+    #[test]
+    fn test_random_bytes_empty() {
+        use std::os::fd::FromRawFd;
+
+        // an invalid fd proves that no read is issued
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(-1) };
+        assert!(rng.random_bytes(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fill_array() {
+        let data: Vec<u8> = (0..32).collect();
+        let (mut rng, _write_fd) = pipe_rng(&data);
+        let key: [u8; 32] = rng.try_fill_array().unwrap();
+        assert_eq!(key[..], data[..]);
+    }
+
+    #[test]
+    fn test_wipe() {
+        let mut buffer = [0xA5u8; 8];
+        crate::wipe(&mut buffer);
+        assert_eq!(buffer, [0u8; 8]);
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_random_zeroizing_bytes() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        let bytes = rng.random_zeroizing_bytes(64).unwrap();
+        assert_eq!(bytes.len(), 64);
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        let mut cloned = rng.clone();
+        assert_ne!(rng.rng_fd, cloned.rng_fd);
+        assert!(rng.try_next_u64().is_ok());
+        assert!(cloned.try_next_u64().is_ok());
+
+        // the clone stays usable after the original is gone
+        drop(rng);
+        assert!(cloned.try_next_u64().is_ok());
+    }
+
+    #[test]
+    fn test_try_clone_dups_fd() {
+        let data = [0x11u8, 0x22u8];
+        let (mut rng, _write_fd) = pipe_rng(&data);
+        let mut cloned = rng.try_clone().unwrap();
+        assert_ne!(rng.rng_fd, cloned.rng_fd);
+
+        let mut byte = [0u8; 1];
+        rng.try_fill_bytes(&mut byte).unwrap();
+        assert_eq!(byte, [0x11]);
+        drop(rng);
+        cloned.try_fill_bytes(&mut byte).unwrap();
+        assert_eq!(byte, [0x22]);
+    }
+
+    #[test]
+    fn test_reopen() {
+        let mut rng = RandJitterKernel::new().unwrap();
+        unsafe { libc::close(rng.rng_fd) };
+        assert!(rng.try_next_u64().is_err());
+
+        rng.reopen().unwrap();
+        assert!(rng.try_next_u64().is_ok());
+    }
+
+    #[test]
+    fn test_ebadf_fails_fast() {
+        use std::os::fd::FromRawFd;
+
+        // no descriptor can have this number, so read() fails with EBADF
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(libc::c_int::MAX) };
+        let mut buffer = [0u8; 8];
+        let err = rng.try_fill_bytes_max_chunk_size(&mut buffer).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    fn test_auto_reopen_on_ebadf() {
+        use std::os::fd::FromRawFd;
+
+        let mut rng =
+            unsafe { RandJitterKernel::from_raw_fd(libc::c_int::MAX) }.with_auto_reopen(true);
+        let mut buffer = [0u8; 8];
+        rng.try_fill_bytes_max_chunk_size(&mut buffer).unwrap();
+        assert_ne!(rng.rng_fd, libc::c_int::MAX);
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        use std::os::fd::FromRawFd;
+
+        // nothing is ever sent, so reading from the pair has to time out
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        rng.set_read_timeout(Some(std::time::Duration::from_millis(10)))
+            .unwrap();
+
+        let mut buffer = [0u8; 8];
+        let err = rng.try_fill_bytes(&mut buffer).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(
+            rng.set_read_timeout(Some(std::time::Duration::ZERO))
+                .is_err()
+        );
+
+        rng.set_read_timeout(None).unwrap();
+        send_packet(peer_fd, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        rng.try_fill_bytes(&mut buffer).unwrap();
+        assert_eq!(buffer, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        unsafe { libc::close(peer_fd) };
+    }
+
+    #[test]
+    fn test_nonblocking() {
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        rng.set_nonblocking(true).unwrap();
+        let flags = unsafe { libc::fcntl(rng.rng_fd, libc::F_GETFL) };
+        assert_ne!(flags & libc::O_NONBLOCK, 0);
+
+        let mut buffer = [0u8; 4];
+        let err = rng.try_fill_bytes(&mut buffer).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+        send_packet(peer_fd, &[1, 2, 3, 4]);
+        rng.try_fill_bytes(&mut buffer).unwrap();
+        assert_eq!(buffer, [1, 2, 3, 4]);
+
+        rng.set_nonblocking(false).unwrap();
+        let flags = unsafe { libc::fcntl(rng.rng_fd, libc::F_GETFL) };
+        assert_eq!(flags & libc::O_NONBLOCK, 0);
+
+        unsafe { libc::close(peer_fd) };
+    }
+
+    #[test]
+    fn test_buffered_u64() {
+        let data: Vec<u8> = (0..16).collect();
+
+        // the first call buffers all 16 bytes with a single read
+        let (mut rng, _write_fd) = pipe_rng(&data);
+        let first = rng.try_next_u64().unwrap();
+        assert_eq!(rng.buffer.available(), 8);
+        let second = rng.try_next_u64().unwrap();
+        assert_eq!(first, u64::from_ne_bytes(data[..8].try_into().unwrap()));
+        assert_eq!(second, u64::from_ne_bytes(data[8..].try_into().unwrap()));
+    }
+
+    #[test]
+    fn test_clear_buffer() {
+        use std::os::fd::AsRawFd;
+
+        let (mut rng, write_fd) = pipe_rng(&[1; 16]);
+        assert_eq!(rng.try_next_u64().unwrap(), u64::from_ne_bytes([1u8; 8]));
+        rng.clear_buffer();
+        assert_eq!(rng.buffer.available(), 0);
+
+        let written = unsafe { libc::write(write_fd.as_raw_fd(), [2u8; 8].as_ptr().cast(), 8) };
+        assert_eq!(written, 8);
+        assert_eq!(rng.try_next_u64().unwrap(), u64::from_ne_bytes([2u8; 8]));
+    }
+
+    #[test]
+    fn test_fill_vectored() {
+        use std::io::IoSliceMut;
+
+        let data: Vec<u8> = (0..300u16).map(|i| (i % 251) as u8 + 1).collect();
+
+        let mut key = [0u8; 32];
+        let mut nonce = [0u8; 12];
+        let mut rest = [0u8; 256];
+        let (mut rng, _write_fd) = pipe_rng(&data);
+        rng.try_fill_vectored(&mut [
+            IoSliceMut::new(&mut key),
+            IoSliceMut::new(&mut []),
+            IoSliceMut::new(&mut nonce),
+            IoSliceMut::new(&mut rest),
+        ])
+        .unwrap();
+
+        assert_eq!(key[..], data[..32]);
+        assert_eq!(nonce[..], data[32..44]);
+        assert_eq!(rest[..], data[44..]);
+    }
+
+    #[test]
+    fn test_with_algorithm() {
+        let mut rng = RandJitterKernel::with_algorithm("jitterentropy_rng").unwrap();
+        assert!(rng.try_next_u64().is_ok());
+    }
+
+    #[test]
+    fn test_with_invalid_algorithm() {
+        for name in ["", "jitter\0entropy_rng", &"a".repeat(64)] {
+            let err = RandJitterKernel::with_algorithm(name).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_drop_invalid_fd() {
+        use std::os::fd::FromRawFd;
+
+        // must neither panic nor call close(-1)
+        drop(unsafe { RandJitterKernel::from_raw_fd(-1) });
+    }
+
+    #[test]
+    fn test_debug_redacts_fd() {
+        use std::os::fd::FromRawFd;
+
+        let rng = unsafe { RandJitterKernel::from_raw_fd(-1) };
+        assert_eq!(format!("{rng:?}"), "RandJitterKernel { open: false }");
+
+        let (read_fd, write_fd) = pipe();
+        let rng = unsafe { RandJitterKernel::from_raw_fd(read_fd) };
+        assert_eq!(format!("{rng:?}"), "RandJitterKernel { open: true }");
+        unsafe { libc::close(write_fd) };
+    }
+
+    #[test]
+    fn test_is_open_and_check() {
+        use std::os::fd::FromRawFd;
+
+        let (read_fd, write_fd) = pipe();
+        let rng = unsafe { RandJitterKernel::from_raw_fd(read_fd) };
+        assert!(rng.is_open());
+        assert!(rng.check().is_ok());
+        unsafe { libc::close(write_fd) };
+
+        let rng = unsafe { RandJitterKernel::from_raw_fd(-1) };
+        assert!(!rng.is_open());
+        assert!(rng.check().is_err());
+
+        let rng = unsafe { RandJitterKernel::from_raw_fd(libc::c_int::MAX) };
+        assert!(rng.is_open());
+        let err = rng.check().unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::InvalidFd)));
+        std::mem::forget(rng);
+    }
+
+    #[test]
+    fn test_self_test() {
+        use std::os::fd::FromRawFd;
+
+        let (read_fd, write_fd) = pipe();
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(read_fd) };
+        let mut sample = [0u8; 32];
+        sample[31] = 1;
+        let written = unsafe { libc::write(write_fd, sample.as_ptr().cast(), sample.len()) };
+        assert_eq!(written, 32);
+        assert!(rng.self_test().is_ok());
+
+        let zeros = [0u8; 32];
+        let written = unsafe { libc::write(write_fd, zeros.as_ptr().cast(), zeros.len()) };
+        assert_eq!(written, 32);
+        let err = rng.self_test().unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::SelfTestFailed)));
+
+        // end of file before the sample is complete
+        unsafe { libc::close(write_fd) };
+        let err = rng.self_test().unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::SourceClosed)));
+    }
+
+    #[test]
+    fn test_is_available() {
+        assert_eq!(is_available(), RandJitterKernel::new().is_ok());
+    }
+
+    #[test]
+    fn test_bytes_generated() {
+        let data = [0x42u8; 200];
+        let (mut rng, write_fd) = pipe_rng(&data);
+
+        assert_eq!(rng.bytes_generated(), 0);
+        let mut buf = [0u8; 100];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert_eq!(rng.bytes_generated(), 100);
+
+        // a small request reads ahead into the buffer
+        rng.try_next_u64().unwrap();
+        assert_eq!(rng.bytes_generated(), 200);
+
+        // a failed read adds nothing
+        drop(write_fd);
+        rng.clear_buffer();
+        assert!(rng.try_fill_bytes(&mut buf).is_err());
+        assert_eq!(rng.bytes_generated(), 200);
+
+        rng.bytes_generated = u64::MAX - 1;
+        rng.count_bytes(16);
+        assert_eq!(rng.bytes_generated(), u64::MAX);
+    }
+
+    #[test]
+    fn test_reset_stats() {
+        let data = [0x42u8; 64];
+        let (mut rng, _write_fd) = pipe_rng(&data);
+
+        let mut buf = [0u8; 32];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert_eq!(rng.bytes_generated(), 32);
+        #[cfg(feature = "stats")]
+        assert_eq!(rng.latency_stats().count(), 1);
+
+        rng.reset_stats();
+        assert_eq!(rng.bytes_generated(), 0);
+        #[cfg(feature = "stats")]
+        assert_eq!(rng.latency_stats(), crate::LatencyStats::default());
+
+        // the descriptor stays usable and counting starts again
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert_eq!(rng.bytes_generated(), 32);
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_log_read_failure() {
+        use std::os::fd::FromRawFd;
+        use std::sync::Mutex;
+
+        struct Collector(Mutex<Vec<String>>);
+        impl log::Log for Collector {
+            fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record<'_>) {
+                let line = format!("{} {}", record.level(), record.args());
+                self.0.lock().unwrap().push(line);
+            }
+            fn flush(&self) {}
+        }
+        static COLLECTOR: Collector = Collector(Mutex::new(Vec::new()));
+        log::set_logger(&COLLECTOR).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(libc::c_int::MAX) };
+        assert!(rng.try_next_u64().is_err());
+        std::mem::forget(rng);
+
+        let expected = format!("ERROR read() failed with errno {}", libc::EBADF);
+        let lines = COLLECTOR.0.lock().unwrap();
+        assert!(lines.iter().any(|line| line.starts_with(&expected)));
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_latency_stats() {
+        let data = [0x42u8; 64];
+        let (mut rng, _write_fd) = pipe_rng(&data);
+
+        assert_eq!(rng.latency_stats().count(), 0);
+        let mut buf = [0u8; 32];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        rng.try_fill_bytes(&mut buf).unwrap();
+        let stats = rng.latency_stats();
+        assert_eq!(stats.count(), 2);
+        assert!(stats.min() <= stats.avg() && stats.avg() <= stats.max());
+    }
+
+    #[test]
+    fn test_next_float() {
+        let mut data = [0u8; 24];
+        data[8..16].fill(0xFF);
+        data[20..24].fill(0xFF);
+        let (mut rng, _write_fd) = pipe_rng(&data);
+
+        // the smallest and the largest possible values
+        assert!((rng.try_next_f64().unwrap() - 0.0).abs() < f64::EPSILON);
+        assert!((rng.try_next_f64().unwrap() - (1.0 - 2f64.powi(-53))).abs() < f64::EPSILON);
+        assert!((rng.try_next_f32().unwrap() - 0.0).abs() < f32::EPSILON);
+        assert!((rng.try_next_f32().unwrap() - (1.0 - 2f32.powi(-24))).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_gen_bool() {
+        let data = [0x01u8, 0xFE];
+        let (mut rng, _write_fd) = pipe_rng(&data);
+
+        assert!(rng.try_gen_bool().unwrap());
+        assert!(!rng.try_gen_bool().unwrap());
+    }
+
+    #[test]
+    fn test_gen_range_u64() {
+        let mut data = Vec::new();
+        for x in [0u64, u64::MAX, 7, 12345] {
+            data.extend_from_slice(&x.to_ne_bytes());
+        }
+        let (mut rng, _write_fd) = pipe_rng(&data);
+
+        // 0 is below the rejection threshold 2^63 - 1, so the next draw is used
+        let span = (1u64 << 63) + 1;
+        assert_eq!(rng.try_gen_range_u64(0..span).unwrap(), (1 << 63) - 2);
+        assert_eq!(rng.try_gen_range_u64(10..20).unwrap(), 17);
+        assert_eq!(rng.try_gen_range_u64(5..6).unwrap(), 5);
+
+        let err = rng.try_gen_range_u64(3..3).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::EmptyRange)));
+    }
+
+    #[test]
+    fn test_boxed() {
+        use crate::DynTryRng;
+
+        let mut write_fds = Vec::new();
+        let mut sources: Vec<Box<dyn DynTryRng<Error = std::io::Error> + Send>> = Vec::new();
+        for byte in [0x11u8, 0x22] {
+            let (rng, write_fd) = pipe_rng(&[byte; 8]);
+            sources.push(rng.boxed());
+            write_fds.push(write_fd);
+        }
+
+        let values: Vec<u64> = sources
+            .iter_mut()
+            .map(|rng| rng.try_next_u64().unwrap())
+            .collect();
+        assert_eq!(values, [0x1111_1111_1111_1111, 0x2222_2222_2222_2222]);
+
+        // trait objects can move to another thread
+        std::thread::spawn(move || drop(sources)).join().unwrap();
+    }
+
+    #[test]
+    fn test_seed_rng() {
+        use rand_chacha::ChaCha20Rng;
+        use rand_core::{RngCore, SeedableRng};
+        use std::os::fd::FromRawFd;
+
+        let (read_fd, write_fd) = pipe();
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(read_fd) };
+        let seed = [0x42u8; 32];
+        let written = unsafe { libc::write(write_fd, seed.as_ptr().cast(), seed.len()) };
+        assert_eq!(written, 32);
+
+        let mut seeded: ChaCha20Rng = rng.seed_rng().unwrap();
+        let mut expected = ChaCha20Rng::from_seed(seed);
+        for _ in 0..16 {
+            assert_eq!(seeded.next_u64(), expected.next_u64());
+        }
+
+        // end of file, no seed left
+        unsafe { libc::close(write_fd) };
+        assert!(rng.seed_rng::<ChaCha20Rng>().is_err());
+    }
+
+    #[test]
+    fn test_injected_reader() {
+        let mut rng = scripted(vec![
+            Ok(vec![0x11; 4]),
+            Err(std::io::ErrorKind::Interrupted.into()),
+            Ok(vec![0x22; 4]),
+            Err(std::io::Error::from_raw_os_error(libc::EIO)),
+        ]);
+        assert!(rng.is_open());
+
+        // the interrupted read is retried
+        let mut buf = [0u8; 8];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert_eq!(buf, [0x11, 0x11, 0x11, 0x11, 0x22, 0x22, 0x22, 0x22]);
+
+        let err = rng.try_fill_bytes(&mut buf).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert_eq!(err.unwrap().raw_os_error(), Some(libc::EIO));
+
+        // an exhausted script reports end of file
+        let err = rng.try_fill_bytes(&mut buf).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::SourceClosed)));
+    }
+
+    #[test]
+    fn test_injected_reader_vectored() {
+        let mut rng = scripted(vec![Ok(vec![0x33; 3]), Ok(vec![0x44; 5])]);
+        let mut first = [0u8; 3];
+        let mut second = [0u8; 5];
+        rng.try_fill_vectored(&mut [
+            std::io::IoSliceMut::new(&mut first),
+            std::io::IoSliceMut::new(&mut second),
+        ])
+        .unwrap();
+        assert_eq!(first, [0x33; 3]);
+        assert_eq!(second, [0x44; 5]);
+        assert!(rng.check().is_err());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn test_from_reader() {
+        struct Constant;
+        impl crate::EntropyReader for Constant {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                buf.fill(0x42);
+                Ok(buf.len())
+            }
+        }
+
+        let mut rng = RandJitterKernel::from_reader(Constant);
+        assert_eq!(rng.try_next_u64().unwrap(), 0x4242_4242_4242_4242);
+        assert_eq!(std::os::fd::AsRawFd::as_raw_fd(&rng), -1);
+    }
+
+    #[test]
+    fn test_reopen_with_reader() {
+        let mut rng = scripted(vec![Ok(vec![0x42; 8])]);
+        let err = rng.reopen().unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::InvalidFd)));
+
+        // no descriptor was opened and the reader still serves reads
+        assert_eq!(rng.rng_fd, -1);
+        assert_eq!(rng.try_next_u64().unwrap(), 0x4242_4242_4242_4242);
+    }
+
+    #[test]
+    fn test_read_size_branches() {
+        struct OverReader;
+        impl crate::reader::EntropyReader for OverReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                Ok(buf.len() + 1)
+            }
+        }
+
+        // complete in a single read
+        let mut rng = scripted(vec![Ok(vec![0x11; 8])]);
+        let mut buf = [0u8; 8];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert_eq!(buf, [0x11; 8]);
+
+        // short reads are continued
+        let mut rng = scripted(vec![Ok(vec![0x22; 3]), Ok(vec![0x33; 5])]);
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert_eq!(buf, [0x22, 0x22, 0x22, 0x33, 0x33, 0x33, 0x33, 0x33]);
+
+        // a reader claiming more bytes than requested
+        let mut rng = RandJitterKernel::with_reader(Box::new(OverReader));
+        let err = rng.try_fill_bytes(&mut buf).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(
+            err,
+            Some(RandJitterError::OverRead {
+                got: 9,
+                requested: 8
+            })
+        ));
+    }
+
+    #[test]
+    fn test_fill_bytes_deadline() {
+        use std::time::{Duration, Instant};
+
+        let mut rng = scripted(vec![Ok(vec![0x11; 128]), Ok(vec![0x22; 128])]);
+        let mut buf = [0u8; 256];
+        let deadline = Instant::now() + Duration::from_secs(10);
+        rng.try_fill_bytes_deadline(&mut buf, deadline).unwrap();
+        assert_eq!(buf[..128], [0x11; 128]);
+        assert_eq!(buf[128..], [0x22; 128]);
+
+        let mut rng = scripted(vec![Ok(vec![0x33; 128])]);
+        let err = rng
+            .try_fill_bytes_deadline(&mut buf, Instant::now())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(
+            err,
+            Some(RandJitterError::DeadlineExceeded {
+                filled: 0,
+                expected: 256
+            })
+        ));
+    }
+
+    #[test]
+    fn test_fill_bytes_deadline_partial() {
+        use std::time::{Duration, Instant};
+
+        struct SlowReader;
+        impl crate::reader::EntropyReader for SlowReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                std::thread::sleep(Duration::from_millis(50));
+                buf.fill(0x44);
+                Ok(buf.len())
+            }
+        }
+
+        let mut rng = RandJitterKernel::with_reader(Box::new(SlowReader));
+        let mut buf = [0u8; 256];
+        let deadline = Instant::now() + Duration::from_millis(10);
+        let err = rng.try_fill_bytes_deadline(&mut buf, deadline).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(
+            err,
+            Some(RandJitterError::DeadlineExceeded {
+                filled: 128,
+                expected: 256
+            })
+        ));
+        assert_eq!(buf[..128], [0x44; 128]);
+        assert_eq!(buf[128..], [0; 128]);
+    }
+
+    #[test]
+    fn test_fd_limit_message() {
+        for errno in [libc::EMFILE, libc::ENFILE] {
+            let err = super::fd_limit_or(
+                std::io::Error::from_raw_os_error(errno),
+                RandJitterError::Accept,
+            );
+            assert!(matches!(err, RandJitterError::FdLimit(_)));
+            assert_eq!(err.raw_os_error(), Some(errno));
+            assert!(err.to_string().contains("RLIMIT_NOFILE"));
+        }
+
+        let err = super::fd_limit_or(
+            std::io::Error::from_raw_os_error(libc::EPERM),
+            RandJitterError::Accept,
+        );
+        assert!(matches!(err, RandJitterError::Accept(_)));
+    }
+
+    #[test]
+    fn test_socket_permission_denied() {
+        for errno in [libc::EPERM, libc::EACCES] {
+            let err = super::socket_error(std::io::Error::from_raw_os_error(errno));
+            assert!(matches!(err, RandJitterError::PermissionDenied(_)));
+            assert_eq!(err.raw_os_error(), Some(errno));
+            assert!(err.to_string().contains("seccomp"));
+            let err = std::io::Error::from(err);
+            assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+        }
+
+        let err = super::socket_error(std::io::Error::from_raw_os_error(libc::EMFILE));
+        assert!(matches!(err, RandJitterError::FdLimit(_)));
+        let err = super::socket_error(std::io::Error::from_raw_os_error(libc::EAFNOSUPPORT));
+        assert!(matches!(err, RandJitterError::SocketCreate(_)));
+    }
+
+    #[test]
+    fn test_buffer_capacity() {
+        // one refill reads several chunks
+        let mut rng = scripted(vec![Ok(vec![0x11; 128]), Ok(vec![0x22; 128])]);
+        rng.buffer = crate::buffer::ReadBuffer::with_chunks(200, 128).unwrap();
+        assert_eq!(rng.try_next_u64().unwrap(), 0x1111_1111_1111_1111);
+        assert_eq!(rng.buffer.available(), 248);
+        let mut buf = [0u8; 8];
+        for _ in 0..15 {
+            rng.fill_buffered(&mut buf).unwrap();
+            assert_eq!(buf, [0x11; 8]);
+        }
+        rng.fill_buffered(&mut buf).unwrap();
+        assert_eq!(buf, [0x22; 8]);
+
+        // a small buffer still returns all bytes in order
+        let mut rng = scripted(vec![Ok((0u8..4).collect()), Ok((4u8..8).collect())]);
+        rng.buffer = crate::buffer::ReadBuffer::with_chunks(4, 4).unwrap();
+        let mut buf = [0u8; 6];
+        rng.fill_buffered(&mut buf).unwrap();
+        assert_eq!(buf, [0, 1, 2, 3, 4, 5]);
+
+        // no buffer, every request reads directly
+        let mut rng = scripted(vec![Ok(vec![0x33; 8]), Ok(vec![0x44; 8])]);
+        rng.buffer = crate::buffer::ReadBuffer::with_chunks(0, 128).unwrap();
+        assert_eq!(rng.try_next_u64().unwrap(), 0x3333_3333_3333_3333);
+        assert_eq!(rng.try_next_u64().unwrap(), 0x4444_4444_4444_4444);
+    }
+
+    #[test]
+    fn test_write_to() {
+        struct FailingWriter;
+        impl std::io::Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::from_raw_os_error(libc::ENOSPC))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut rng = scripted(vec![Ok(vec![0x11; 128]), Ok(vec![0x22; 72])]);
+        let mut out = Vec::new();
+        rng.write_to(&mut out, 200).unwrap();
+        assert_eq!(out[..128], [0x11; 128]);
+        assert_eq!(out[128..], [0x22; 72]);
+
+        let mut rng = scripted(vec![Ok(vec![0x33; 8])]);
+        let err = rng.write_to(&mut FailingWriter, 8).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::Write(_))));
+        assert_eq!(err.unwrap().raw_os_error(), Some(libc::ENOSPC));
+
+        let mut rng = scripted(vec![Err(std::io::Error::from_raw_os_error(libc::EIO))]);
+        let err = rng.write_to(&mut out, 8).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::Read(_))));
+    }
+
+    #[test]
+    fn test_fill_uninit() {
+        use std::mem::MaybeUninit;
+
+        let data: Vec<u8> = (0..=255).collect();
+        let (mut rng, write_fd) = pipe_rng(&data);
+
+        let mut buf = [MaybeUninit::<u8>::uninit(); 200];
+        let filled = rng.try_fill_uninit(&mut buf).unwrap();
+        assert_eq!(filled, &data[..200]);
+        let mut buf = [MaybeUninit::<u8>::uninit(); 100];
+        drop(write_fd);
+        let err = rng.try_fill_uninit(&mut buf).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::SourceClosed)));
+
+        let mut rng = scripted(vec![Ok(vec![0x11; 5]), Ok(vec![0x22; 3])]);
+        let mut buf = [MaybeUninit::<u8>::uninit(); 8];
+        let filled = rng.try_fill_uninit(&mut buf).unwrap();
+        assert_eq!(filled, [0x11, 0x11, 0x11, 0x11, 0x11, 0x22, 0x22, 0x22]);
+        assert!(rng.try_fill_uninit(&mut []).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_is_seeded() {
+        use std::time::Duration;
+
+        struct SlowReader;
+        impl crate::reader::EntropyReader for SlowReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                std::thread::sleep(Duration::from_millis(20));
+                buf.fill(0x42);
+                Ok(buf.len())
+            }
+        }
+
+        let mut rng = scripted(vec![Ok(vec![0x11; 32])]);
+        assert!(rng.is_seeded().unwrap());
+        assert!(rng.is_seeded().is_err());
+
+        let mut rng = RandJitterKernel::with_reader(Box::new(SlowReader));
+        assert!(!rng.is_seeded_within(Duration::from_millis(10)).unwrap());
+        assert!(rng.is_seeded_within(Duration::from_secs(10)).unwrap());
+    }
+
+    #[test]
+    fn test_display() {
+        let mut rng = scripted(vec![Ok(vec![0x42; 16])]);
+        rng.try_fill_bytes(&mut [0u8; 16]).unwrap();
+        assert_eq!(
+            rng.to_string(),
+            "jitterentropy_rng(fd=reader, generated=16 bytes)"
+        );
+
+        let (read_fd, write_fd) = pipe();
+        let mut rng = unsafe { <RandJitterKernel as std::os::fd::FromRawFd>::from_raw_fd(read_fd) };
+        rng.options.algorithm =
+            crate::algorithm::AlgorithmName::new("drbg_nopr_hmac_sha256").unwrap();
+        assert_eq!(
+            rng.to_string(),
+            "drbg_nopr_hmac_sha256(fd=open, generated=0 bytes)"
+        );
+        unsafe { libc::close(write_fd) };
+
+        let rng = unsafe { <RandJitterKernel as std::os::fd::FromRawFd>::from_raw_fd(-1) };
+        assert_eq!(
+            rng.to_string(),
+            "jitterentropy_rng(fd=closed, generated=0 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_fill_exact() {
+        use std::io::Read;
+
+        let mut rng = scripted(vec![Ok(vec![0x42; 128]), Ok(vec![0x17; 128])]);
+        let mut buf = [0u8; 200];
+        assert_eq!(rng.read(&mut buf).unwrap(), 128);
+
+        let mut rng = scripted(vec![Ok(vec![0x42; 128]), Ok(vec![0x17; 72])]);
+        rng.fill_exact(&mut buf).unwrap();
+        assert!(buf[..128].iter().all(|&b| b == 0x42));
+        assert!(buf[128..].iter().all(|&b| b == 0x17));
+        assert!(rng.fill_exact(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_try_fill_bytes_partial() {
+        let mut rng = scripted(vec![
+            Ok(vec![0x42; 128]),
+            Ok(vec![0x17; 128]),
+            Err(std::io::Error::from_raw_os_error(libc::EIO)),
+        ]);
+        let mut buf = [0u8; 300];
+        let (filled, err) = rng.try_fill_bytes_partial(&mut buf).unwrap_err();
+        assert_eq!(filled, 256);
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert_eq!(err.unwrap().raw_os_error(), Some(libc::EIO));
+        assert!(buf[..128].iter().all(|&b| b == 0x42));
+        assert!(buf[128..256].iter().all(|&b| b == 0x17));
+
+        let mut rng = scripted(vec![Ok(vec![0x42; 44])]);
+        assert_eq!(rng.try_fill_bytes_partial(&mut buf[256..]).unwrap(), 44);
+        assert!(buf[256..].iter().all(|&b| b == 0x42));
+    }
+
+    #[test]
+    fn test_close() {
+        use std::os::fd::FromRawFd;
+
+        let (fd, write_fd) = pipe();
+        let rng = unsafe { RandJitterKernel::from_raw_fd(fd) };
+        rng.close().unwrap();
+        unsafe { libc::close(write_fd) };
+
+        // far above any descriptor the test process opens
+        let rng = unsafe { RandJitterKernel::from_raw_fd(1 << 20) };
+        let err = rng.close().unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::Close(_))));
+        assert_eq!(err.unwrap().raw_os_error(), Some(libc::EBADF));
+
+        let rng = unsafe { RandJitterKernel::from_raw_fd(-1) };
+        assert!(rng.close().is_err());
+        assert!(scripted(vec![]).close().is_ok());
+    }
+
+    #[test]
+    fn test_move_fd_above() {
+        use std::os::fd::FromRawFd;
+
+        let (read_fd, write_fd) = pipe();
+        assert_eq!(move_fd_above(read_fd, 0, true).unwrap(), read_fd);
+
+        let moved = move_fd_above(read_fd, read_fd.max(write_fd) + 1, true).unwrap();
+        assert!(moved > write_fd);
+        let flags = unsafe { libc::fcntl(moved, libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+
+        let data = [0x42u8; 8];
+        let written = unsafe { libc::write(write_fd, data.as_ptr().cast(), data.len()) };
+        assert_eq!(written, 8);
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(moved) };
+        assert_eq!(rng.try_next_u64().unwrap(), 0x4242_4242_4242_4242);
+        unsafe { libc::close(write_fd) };
+
+        assert!(move_fd_above(-1, MIN_NON_STDIO_FD, true).is_err());
+    }
+
+    #[test]
+    fn test_family_fd() {
+        use std::os::fd::AsRawFd;
+
+        let fam_fd = RandJitterKernel::create_family_fd().unwrap();
+        let mut first = RandJitterKernel::from_family_fd(fam_fd.as_raw_fd()).unwrap();
+        let mut second = RandJitterKernel::from_family_fd(fam_fd.as_raw_fd()).unwrap();
+        assert!(first.try_next_u64().is_ok());
+        assert!(second.try_next_u64().is_ok());
+    }
+
+    #[test]
+    fn test_from_family_fd_invalid() {
+        let err = RandJitterKernel::from_family_fd(-1).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::Accept(_))));
+        assert_eq!(err.unwrap().raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    fn test_read_size_cases() {
+        // read() returning -1 surfaces errno
+        let mut rng = scripted(vec![Err(std::io::Error::from_raw_os_error(libc::EIO))]);
+        let err = rng
+            .try_fill_bytes_max_chunk_size(&mut [0u8; 16])
+            .unwrap_err();
+        assert!(matches!(err, RandJitterError::Read(_)));
+        assert_eq!(err.raw_os_error(), Some(libc::EIO));
+
+        // read() returning 0 is end of file
+        let mut rng = scripted(vec![Ok(vec![])]);
+        let err = rng
+            .try_fill_bytes_max_chunk_size(&mut [0u8; 16])
+            .unwrap_err();
+        assert!(matches!(err, RandJitterError::SourceClosed));
+
+        // read() returning exactly the requested length completes in one call
+        let mut rng = scripted(vec![Ok(vec![0x42; 16]), Ok(vec![])]);
+        let mut buf = [0u8; 16];
+        rng.try_fill_bytes_max_chunk_size(&mut buf).unwrap();
+        assert_eq!(buf, [0x42; 16]);
+        assert_eq!(rng.bytes_generated(), 16);
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let script = (0..32).map(|_| Ok(vec![0x42; 128])).collect();
+        let mut rng = scripted(script);
+        rng.warm_up().unwrap();
+        assert_eq!(rng.bytes_generated(), 4096);
+
+        let mut rng = scripted(vec![Ok(vec![0x42; 128]), Ok(vec![0x42; 72])]);
+        rng.warm_up_bytes(200).unwrap();
+        assert_eq!(rng.bytes_generated(), 200);
+        assert!(rng.warm_up_bytes(1).is_err());
+    }
+
+    #[test]
+    fn test_algorithm_name() {
+        use std::os::fd::FromRawFd;
+
+        let rng = scripted(vec![]);
+        assert_eq!(rng.algorithm_name().unwrap(), "jitterentropy_rng");
+
+        let rng = unsafe { RandJitterKernel::from_raw_fd(-1) };
+        assert!(rng.algorithm_name().is_err());
+    }
+
+    #[test]
+    fn test_fill_huge_chunk_size() {
+        let mut rng = scripted(vec![Ok(vec![0x42; 100]), Ok(vec![0x17; 100])]);
+        rng.max_chunk_size = usize::MAX;
+        let mut buf = [0u8; 200];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert!(buf[..100].iter().all(|&b| b == 0x42));
+        assert!(buf[100..].iter().all(|&b| b == 0x17));
+
+        // a chunk size that does not divide the length leaves a short last chunk
+        let mut rng = scripted(vec![Ok(vec![0x42; 128]), Ok(vec![0x17; 72])]);
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert!(buf[128..].iter().all(|&b| b == 0x17));
+    }
+
+    #[test]
+    fn test_u32_takes_4_bytes() {
+        let mut rng = scripted(vec![Ok(vec![
+            0x11, 0x11, 0x11, 0x11, 0x22, 0x22, 0x22, 0x22,
+        ])]);
+        assert_eq!(rng.try_next_u32().unwrap(), 0x1111_1111);
+        assert_eq!(rng.try_next_u32().unwrap(), 0x2222_2222);
+        assert!(rng.try_next_u32().is_err());
+
+        let mut rng = scripted(vec![Ok(vec![0x33; 12])]);
+        assert_eq!(rng.try_next_u32().unwrap(), 0x3333_3333);
+        assert_eq!(rng.try_next_u64().unwrap(), 0x3333_3333_3333_3333);
+    }
+
+    #[test]
+    fn test_u32_full_range() {
+        // every bit of the 4 bytes ends up in the value, none is masked off or truncated
+        let mut rng = scripted(vec![Ok(vec![0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0])]);
+        assert_eq!(rng.try_next_u32().unwrap(), u32::MAX);
+        assert_eq!(rng.try_next_u32().unwrap(), 0);
+
+        let mut rng = scripted(vec![Ok(vec![0x80, 0, 0, 0x01])]);
+        assert_eq!(
+            rng.try_next_u32().unwrap(),
+            u32::from_ne_bytes([0x80, 0, 0, 0x01])
+        );
+    }
+
+    #[test]
+    fn test_explicit_endianness() {
+        let mut bytes = [1, 2, 3, 4].repeat(3);
+        bytes.extend([1, 2, 3, 4, 5, 6, 7, 8].repeat(2));
+        let mut rng = scripted(vec![Ok(bytes)]);
+        assert_eq!(rng.try_next_u32_le().unwrap(), 0x0403_0201);
+        assert_eq!(rng.try_next_u32_be().unwrap(), 0x0102_0304);
+        assert_eq!(
+            rng.try_next_u32().unwrap(),
+            u32::from_ne_bytes([1, 2, 3, 4])
+        );
+        assert_eq!(rng.try_next_u64_le().unwrap(), 0x0807_0605_0403_0201);
+        assert_eq!(rng.try_next_u64_be().unwrap(), 0x0102_0304_0506_0708);
+        assert!(rng.try_next_u64_le().is_err());
+    }
+
+    #[test]
+    fn test_fill_u64() {
+        let bytes: Vec<u8> = (1..=32).collect();
+        let mut rng = scripted(vec![Ok(bytes.clone())]);
+        let mut state = [0u64; 4];
+        rng.try_fill_u64(&mut state).unwrap();
+        assert!(state.iter().all(|&x| x != 0));
+        for (value, chunk) in state.iter().zip(bytes.chunks(8)) {
+            assert_eq!(*value, u64::from_ne_bytes(chunk.try_into().unwrap()));
+        }
+
+        assert!(rng.try_fill_u64(&mut []).is_ok());
+        assert!(rng.try_fill_u64(&mut state).is_err());
+    }
+
+    /// writes `len` bytes counting up from 1 into a new pipe and returns an rng reading them
+    fn counting_pipe(len: usize) -> (RandJitterKernel, std::os::fd::OwnedFd) {
+        #[allow(clippy::cast_possible_truncation)]
+        let data: Vec<u8> = (1..=len).map(|i| i as u8).collect();
+        pipe_rng(&data)
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_fill_sub_slices() {
+        const GUARD: u8 = 0xEE;
+
+        // odd offsets and lengths around the chunk size, including several chunks
+        for (offset, len) in [(1, 1), (3, 127), (7, 128), (1, 129), (5, 300), (13, 1000)] {
+            let (mut rng, _write_fd) = counting_pipe(len);
+            let mut buf = vec![GUARD; offset + len + 16];
+            rng.try_fill_bytes(&mut buf[offset..offset + len]).unwrap();
+
+            assert!(buf[..offset].iter().all(|&b| b == GUARD));
+            assert!(buf[offset + len..].iter().all(|&b| b == GUARD));
+            for (i, &b) in buf[offset..offset + len].iter().enumerate() {
+                assert_eq!(b, (i + 1) as u8, "offset {offset}, len {len}, index {i}");
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_fill_uninit_sub_slices() {
+        use std::mem::MaybeUninit;
+        const GUARD: u8 = 0xEE;
+
+        for (offset, len) in [(1, 1), (3, 129), (13, 300)] {
+            let (mut rng, _write_fd) = counting_pipe(len);
+            let mut buf = vec![MaybeUninit::new(GUARD); offset + len + 16];
+            let filled = rng.try_fill_uninit(&mut buf[offset..offset + len]).unwrap();
+            assert_eq!(filled.len(), len);
+            for (i, &b) in filled.iter().enumerate() {
+                assert_eq!(b, (i + 1) as u8, "offset {offset}, len {len}, index {i}");
+            }
+
+            // the guard bytes were initialized and must not have been written
+            let guards = buf[..offset].iter().chain(&buf[offset + len..]);
+            assert!(
+                guards
+                    .map(|b| unsafe { b.assume_init() })
+                    .all(|b| b == GUARD)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fill_u64_sub_slices() {
+        const GUARD: u64 = 0xEEEE_EEEE_EEEE_EEEE;
+
+        // 17 values span more than one chunk of 128 byte
+        for (offset, len) in [(1, 1), (3, 16), (5, 17)] {
+            let (mut rng, _write_fd) = counting_pipe(len * 8);
+            let mut buf = vec![GUARD; offset + len + 2];
+            rng.try_fill_u64(&mut buf[offset..offset + len]).unwrap();
+
+            assert!(buf[..offset].iter().all(|&v| v == GUARD));
+            assert!(buf[offset + len..].iter().all(|&v| v == GUARD));
+            for (i, v) in buf[offset..offset + len].iter().enumerate() {
+                let first = u8::try_from((i * 8 + 1) % 256).unwrap();
+                assert_eq!(
+                    v.to_ne_bytes()[0],
+                    first,
+                    "offset {offset}, len {len}, index {i}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::used_underscore_binding)] // the slot is only held, never read outside tests
+    fn test_reopen_at_instance_limit() {
+        use std::os::fd::FromRawFd;
+        static LIMIT: crate::limit::InstanceLimit = crate::limit::InstanceLimit::new();
+
+        LIMIT.set_max(1);
+        crate::limit::TEST_LIMIT.with(|limit| limit.set(Some(&LIMIT)));
+        let (read_fd, write_fd) = pipe();
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(read_fd) };
+        rng._slot = Some(crate::limit::acquire().unwrap());
+        assert_eq!(LIMIT.open(), 1);
+
+        // either the kernel hands out a fresh instance, or opening it fails on this host, but
+        // never for lack of a slot
+        let result = rng.reopen();
+        if let Err(err) = &result {
+            let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+            assert!(!matches!(
+                err,
+                Some(RandJitterError::TooManyInstances { .. })
+            ));
+        }
+        assert_eq!(LIMIT.open(), 1);
+        assert!(rng._slot.is_some());
+
+        crate::limit::TEST_LIMIT.with(|limit| limit.set(None));
+        unsafe { libc::close(write_fd) };
+        drop(rng);
+        assert_eq!(LIMIT.open(), 0);
+    }
+
+    #[test]
+    fn test_fill_vectored_shares_read_errors() {
+        use std::io::IoSliceMut;
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+        rng.set_read_timeout(Some(std::time::Duration::from_millis(10)))
+            .unwrap();
+        let (mut key, mut nonce) = ([0u8; 16], [0u8; 8]);
+        let mut bufs = [IoSliceMut::new(&mut key), IoSliceMut::new(&mut nonce)];
+        let err = rng.try_fill_vectored(&mut bufs).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+        send_packet(peer_fd, &[0x42; 24]);
+        rng.try_fill_vectored(&mut bufs).unwrap();
+        assert_eq!(rng.bytes_generated(), 24);
+        #[cfg(feature = "stats")]
+        assert_eq!(rng.latency_stats().count(), 1);
+
+        unsafe { libc::close(peer_fd) };
+        let err = rng.try_fill_vectored(&mut bufs).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<RandJitterError>();
+        assert!(matches!(err, Some(RandJitterError::SourceClosed)));
     }
 
     #[test]
@@ -0,0 +1,101 @@
+//! Process-wide limit on the number of open rng instances
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::RandJitterError;
+
+/// limit shared by all instances of the process
+pub(crate) static GLOBAL_LIMIT: InstanceLimit = InstanceLimit::new();
+
+#[cfg(test)]
+thread_local! {
+    /// replaces [`GLOBAL_LIMIT`] for new slots of the current thread, so a test can run at the
+    /// limit without failing tests running in parallel
+    pub(crate) static TEST_LIMIT: std::cell::Cell<Option<&'static InstanceLimit>> =
+        const { std::cell::Cell::new(None) };
+}
+
+/// takes a slot of [`GLOBAL_LIMIT`] for a new instance
+pub(crate) fn acquire() -> Result<InstanceSlot, RandJitterError> {
+    #[cfg(test)]
+    if let Some(limit) = TEST_LIMIT.with(std::cell::Cell::get) {
+        return limit.acquire();
+    }
+    GLOBAL_LIMIT.acquire()
+}
+
+/// counter of open instances with an upper bound, `usize::MAX` disables the bound
+#[derive(Debug)]
+pub(crate) struct InstanceLimit {
+    open: AtomicUsize,
+    max: AtomicUsize,
+}
+
+impl InstanceLimit {
+    pub(crate) const fn new() -> Self {
+        InstanceLimit {
+            open: AtomicUsize::new(0),
+            max: AtomicUsize::new(usize::MAX),
+        }
+    }
+
+    pub(crate) fn set_max(&self, max: usize) {
+        self.max.store(max, Ordering::Relaxed);
+    }
+
+    pub(crate) fn open(&self) -> usize {
+        self.open.load(Ordering::Relaxed)
+    }
+
+    /// takes a slot, which is given back when dropped
+    pub(crate) fn acquire(&'static self) -> Result<InstanceSlot, RandJitterError> {
+        let max = self.max.load(Ordering::Relaxed);
+        self.open
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |open| {
+                (open < max).then_some(open + 1)
+            })
+            .map_err(|_| RandJitterError::TooManyInstances { max })?;
+        Ok(InstanceSlot { limit: self })
+    }
+}
+
+/// one open instance counted against an [`InstanceLimit`]
+#[derive(Debug)]
+pub(crate) struct InstanceSlot {
+    limit: &'static InstanceLimit,
+}
+
+impl Drop for InstanceSlot {
+    fn drop(&mut self) {
+        self.limit.open.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InstanceLimit;
+    use crate::RandJitterError;
+
+    #[test]
+    fn test_instance_limit() {
+        static LIMIT: InstanceLimit = InstanceLimit::new();
+        LIMIT.set_max(2);
+
+        let first = LIMIT.acquire().unwrap();
+        let second = LIMIT.acquire().unwrap();
+        assert_eq!(LIMIT.open(), 2);
+        assert!(matches!(
+            LIMIT.acquire(),
+            Err(RandJitterError::TooManyInstances { max: 2 })
+        ));
+
+        drop(first);
+        assert_eq!(LIMIT.open(), 1);
+        let _third = LIMIT.acquire().unwrap();
+
+        LIMIT.set_max(usize::MAX);
+        let _fourth = LIMIT.acquire().unwrap();
+        assert_eq!(LIMIT.open(), 3);
+        drop(second);
+    }
+}
@@ -0,0 +1,91 @@
+//! Wrapper pinning an rng instance to the thread that created it
+
+use std::marker::PhantomData;
+
+use rand_core::{TryCryptoRng, TryRngCore};
+
+use crate::RandJitterKernel;
+
+/// rng instance that can neither be sent to nor shared with other threads
+///
+/// [`RandJitterKernel`] itself is `Send`, so it may be moved to another thread. This wrapper
+/// rejects that at compile time for code that wants to enforce single-thread use, and like the
+/// plain instance needs no locking. Use [`crate::SharedRandJitterKernel`] to share one instance.
+#[derive(Debug)]
+pub struct PerThread {
+    rng: RandJitterKernel,
+    // raw pointers are neither `Send` nor `Sync`
+    _not_send: PhantomData<*const ()>,
+}
+
+impl PerThread {
+    /// constructs new RNG instance bound to the current thread
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`].
+    pub fn new() -> Result<Self, std::io::Error> {
+        Ok(Self::from(RandJitterKernel::new()?))
+    }
+
+    /// returns the wrapped instance, which may be sent to other threads again
+    #[must_use]
+    pub fn into_inner(self) -> RandJitterKernel {
+        self.rng
+    }
+}
+
+impl From<RandJitterKernel> for PerThread {
+    fn from(rng: RandJitterKernel) -> Self {
+        PerThread {
+            rng,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl TryRngCore for PerThread {
+    type Error = std::io::Error;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        self.rng.try_next_u32()
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        self.rng.try_next_u64()
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        self.rng.try_fill_bytes(dst)
+    }
+}
+
+impl TryCryptoRng for PerThread {}
+
+#[cfg(test)]
+mod tests {
+    use crate::PerThread;
+    use crate::test_support::pipe_rng;
+    use rand_core::TryRngCore;
+
+    #[test]
+    fn test_per_thread_not_send() {
+        // only compiles if the blanket impl for `Send` types does not apply, as `_` would be
+        // ambiguous otherwise
+        trait AmbiguousIfSend<A> {
+            fn some_item() {}
+        }
+        impl<T: ?Sized> AmbiguousIfSend<()> for T {}
+        impl<T: ?Sized + Send> AmbiguousIfSend<u8> for T {}
+
+        <PerThread as AmbiguousIfSend<_>>::some_item();
+    }
+
+    #[test]
+    fn test_per_thread_from_fd() {
+        let (rng, _write_fd) = pipe_rng(&[0x42; 8]);
+        let mut rng = PerThread::from(rng);
+        assert_eq!(rng.try_next_u64().unwrap(), 0x4242_4242_4242_4242);
+        let rng = rng.into_inner();
+        std::thread::spawn(move || drop(rng)).join().unwrap();
+    }
+}
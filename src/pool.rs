@@ -0,0 +1,138 @@
+//! Pool handing out many rng instances from a single bound `AF_ALG` socket
+
+use rand_core::TryRngCore;
+
+use crate::{
+    RandJitterKernel, SocketType, algorithm::AlgorithmName, builder::Options, open_family_fd,
+};
+
+/// holds the bound `AF_ALG` family socket open, so new instances only need a cheap `accept()`
+#[derive(Debug)]
+pub struct RandJitterKernelPool {
+    fam_fd: libc::c_int,
+}
+
+impl RandJitterKernelPool {
+    /// creates the `AF_ALG` socket and binds it to `jitterentropy_rng`
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`].
+    pub fn new() -> Result<Self, std::io::Error> {
+        Ok(RandJitterKernelPool {
+            fam_fd: open_family_fd(true, &AlgorithmName::default(), SocketType::default())?,
+        })
+    }
+
+    /// constructs a new RNG instance by accepting on the bound family socket
+    ///
+    /// # Errors
+    /// Returns [`crate::RandJitterError::Accept`] wrapped inside a `std::io::Error` if the kernel
+    /// does not hand out another instance.
+    pub fn acquire(&self) -> Result<RandJitterKernel, std::io::Error> {
+        Ok(RandJitterKernel::accept_from(
+            self.fam_fd,
+            &Options::default(),
+        )?)
+    }
+}
+
+impl RandJitterKernel {
+    /// constructs `count` RNG instances sharing one bound family socket, which is closed again
+    /// before returning
+    ///
+    /// Each instance only costs an `accept()`, which is much cheaper than the `socket()` and
+    /// `bind()` of `count` calls to [`RandJitterKernel::new`]. The instances are independent and
+    /// stay usable after the family socket is closed.
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernelPool::new`] and [`RandJitterKernelPool::acquire`]. If an accept
+    /// fails, the instances created so far are closed again.
+    pub fn batch_new(count: usize) -> Result<Vec<RandJitterKernel>, std::io::Error> {
+        let pool = RandJitterKernelPool::new()?;
+        (0..count).map(|_| pool.acquire()).collect()
+    }
+
+    /// fills `dst` using `instances` instances from one pool, each filling a disjoint slice on
+    /// its own thread
+    ///
+    /// All instances share the kernel's entropy source, so throughput does not necessarily scale
+    /// linearly with the number of instances. `instances` is at least 1 and at most one instance
+    /// per chunk of `dst` is used.
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernelPool::acquire`] and [`TryRngCore::try_fill_bytes`]. If a thread
+    /// fails, the first error is returned and `dst` is only partially filled.
+    pub fn parallel_fill(dst: &mut [u8], instances: usize) -> Result<(), std::io::Error> {
+        if dst.is_empty() {
+            return Ok(());
+        }
+
+        let pool = RandJitterKernelPool::new()?;
+        let instances = instances.clamp(1, dst.len().div_ceil(crate::MAX_RETURN_CHUNK_SIZE));
+        let slice_len = dst.len().div_ceil(instances);
+        let rngs = (0..instances)
+            .map(|_| pool.acquire())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        std::thread::scope(|scope| {
+            let threads: Vec<_> = rngs
+                .into_iter()
+                .zip(dst.chunks_mut(slice_len))
+                .map(|(mut rng, slice)| scope.spawn(move || rng.try_fill_bytes(slice)))
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| {
+                    thread
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect::<Result<Vec<()>, _>>()
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Drop for RandJitterKernelPool {
+    fn drop(&mut self) {
+        crate::sys::close(self.fam_fd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RandJitterKernel, RandJitterKernelPool};
+    use rand_core::TryRngCore;
+
+    #[test]
+    fn test_acquire() {
+        let pool = RandJitterKernelPool::new().unwrap();
+        for _ in 0..16 {
+            let mut rng = pool.acquire().unwrap();
+            assert!(rng.try_next_u64().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_batch_new() {
+        let mut rngs = RandJitterKernel::batch_new(16).unwrap();
+        assert_eq!(rngs.len(), 16);
+        for rng in &mut rngs {
+            assert!(rng.try_next_u64().is_ok());
+        }
+        assert!(RandJitterKernel::batch_new(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parallel_fill() {
+        let mut buf = vec![0u8; 4096];
+        RandJitterKernel::parallel_fill(&mut buf, 4).unwrap();
+        // every slice of 1024 byte was written
+        for slice in buf.chunks(1024) {
+            assert!(slice.iter().any(|&b| b != 0));
+        }
+        RandJitterKernel::parallel_fill(&mut [], 4).unwrap();
+        RandJitterKernel::parallel_fill(&mut buf[..10], 0).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+//! Background thread prefetching entropy, so foreground reads rarely wait for the kernel
+
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::thread::JoinHandle;
+
+use rand_core::{TryCryptoRng, TryRngCore};
+
+use crate::{MAX_RETURN_CHUNK_SIZE, RandJitterKernel, wipe};
+
+/// number of chunks the background thread reads ahead by default
+pub const DEFAULT_PREFETCH_DEPTH: usize = 8;
+
+/// one chunk of entropy, wiped when dropped
+struct Chunk([u8; MAX_RETURN_CHUNK_SIZE]);
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+type ChunkResult = Result<Chunk, std::io::Error>;
+
+/// rng served from chunks read ahead by a background thread
+///
+/// The thread owns the [`RandJitterKernel`] and keeps up to `depth` chunks of
+/// `MAX_RETURN_CHUNK_SIZE` bytes ready. It stops after the first read error, which is handed
+/// to the foreground, and when this struct is dropped. Dropping waits for a read in progress.
+pub struct PrefetchingRng {
+    receiver: Option<Receiver<ChunkResult>>,
+    current: Chunk,
+    pos: usize,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PrefetchingRng {
+    /// constructs new RNG instance and starts prefetching [`DEFAULT_PREFETCH_DEPTH`] chunks
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`], or the error of spawning the thread.
+    pub fn new() -> Result<Self, std::io::Error> {
+        Self::from_rng(RandJitterKernel::new()?, DEFAULT_PREFETCH_DEPTH)
+    }
+
+    /// moves `rng` into a background thread keeping up to `depth` chunks ready
+    ///
+    /// # Errors
+    /// Returns the error of spawning the thread.
+    pub fn from_rng(rng: RandJitterKernel, depth: usize) -> Result<Self, std::io::Error> {
+        let (sender, receiver) = sync_channel(depth);
+        let thread = std::thread::Builder::new()
+            .name("rand_jitter_prefetch".to_string())
+            .spawn(move || prefetch(rng, &sender))?;
+
+        Ok(PrefetchingRng {
+            receiver: Some(receiver),
+            current: Chunk([0u8; MAX_RETURN_CHUNK_SIZE]),
+            pos: MAX_RETURN_CHUNK_SIZE,
+            thread: Some(thread),
+        })
+    }
+
+    fn next_chunk(&mut self) -> Result<(), std::io::Error> {
+        let chunk = self
+            .receiver
+            .as_ref()
+            .and_then(|receiver| receiver.recv().ok())
+            .ok_or_else(|| std::io::Error::other("prefetch thread of jitterentropy_rng stopped"))?;
+        self.current = chunk?;
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+fn prefetch(mut rng: RandJitterKernel, sender: &SyncSender<ChunkResult>) {
+    loop {
+        let mut chunk = Chunk([0u8; MAX_RETURN_CHUNK_SIZE]);
+        let result = rng.try_fill_bytes(&mut chunk.0).map(|()| chunk);
+        let failed = result.is_err();
+        // sending fails once the foreground is gone
+        if sender.send(result).is_err() || failed {
+            return;
+        }
+    }
+}
+
+impl TryRngCore for PrefetchingRng {
+    type Error = std::io::Error;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        let mut bytes = [0u8; 4];
+        let result = self.try_fill_bytes(&mut bytes);
+        let value = u32::from_ne_bytes(bytes);
+        wipe(&mut bytes);
+
+        result.map(|()| value)
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        let mut bytes = [0u8; 8];
+        let result = self.try_fill_bytes(&mut bytes);
+        let value = u64::from_ne_bytes(bytes);
+        wipe(&mut bytes);
+
+        result.map(|()| value)
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        let mut filled = 0;
+        while filled < dst.len() {
+            if self.pos == self.current.0.len() {
+                self.next_chunk()?;
+            }
+
+            let len = (dst.len() - filled).min(self.current.0.len() - self.pos);
+            let taken = &mut self.current.0[self.pos..self.pos + len];
+            dst[filled..filled + len].copy_from_slice(taken);
+            wipe(taken);
+            self.pos += len;
+            filled += len;
+        }
+
+        Ok(())
+    }
+}
+
+impl TryCryptoRng for PrefetchingRng {}
+
+impl std::fmt::Debug for PrefetchingRng {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrefetchingRng")
+            .field("running", &self.thread.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for PrefetchingRng {
+    fn drop(&mut self) {
+        // the thread notices the dropped receiver on its next send and stops
+        drop(self.receiver.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PrefetchingRng;
+    use crate::test_support::pipe_rng;
+    use rand_core::TryRngCore;
+
+    #[test]
+    fn test_prefetch() {
+        let mut rng = PrefetchingRng::new().unwrap();
+        let mut buffer = [0u8; 1000];
+        rng.try_fill_bytes(&mut buffer).unwrap();
+        assert!(rng.try_next_u64().is_ok());
+    }
+
+    #[test]
+    fn test_prefetch_stops_on_error() {
+        let data: Vec<u8> = (0..=255).collect();
+        let (rng, write_fd) = pipe_rng(&data);
+        drop(write_fd);
+        let mut rng = PrefetchingRng::from_rng(rng, 1).unwrap();
+        let mut buffer = [0u8; 200];
+        rng.try_fill_bytes(&mut buffer).unwrap();
+        assert_eq!(buffer[..], data[..200]);
+        rng.try_fill_bytes(&mut buffer[..56]).unwrap();
+        assert_eq!(buffer[..56], data[200..]);
+
+        // the pipe hit end of file, the thread reports it and stops
+        assert!(rng.try_next_u32().is_err());
+        assert!(rng.try_next_u32().is_err());
+    }
+}
@@ -0,0 +1,28 @@
+//! Source of the bytes returned by an rng instance
+
+/// source of random bytes behind a [`crate::RandJitterKernel`]
+///
+/// Instances created by `new()` read from their `AF_ALG` descriptor. With the `test-util`
+/// feature, [`crate::RandJitterKernel::from_reader`] injects any other reader instead, so code
+/// consuming the rng can be tested deterministically, including its error handling.
+pub trait EntropyReader: Send + Sync {
+    /// reads up to `buf.len()` bytes into `buf` and returns how many were read, like
+    /// `std::io::Read::read`
+    ///
+    /// # Errors
+    /// Errors are handled like those of `read()` on the descriptor, e.g.
+    /// `ErrorKind::Interrupted` is retried.
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>;
+}
+
+/// issues a single `read()` of up to `len` bytes on `fd` into `buf`
+///
+/// # Safety
+/// `buf` must be valid for writes of `len` bytes, which may be uninitialized.
+pub(crate) unsafe fn read_fd_raw(
+    fd: libc::c_int,
+    buf: *mut u8,
+    len: usize,
+) -> std::io::Result<usize> {
+    unsafe { crate::sys::read(fd, buf, len) }
+}
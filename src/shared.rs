@@ -0,0 +1,144 @@
+//! Thread-safe handle sharing one rng instance between threads
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use rand_core::{TryCryptoRng, TryRngCore};
+
+use crate::RandJitterKernel;
+
+/// rng instance behind an internal lock, usable from many threads e.g. through an `Arc`
+///
+/// All threads are serialized on one descriptor, so heavily contended use is slower than giving
+/// every thread its own [`RandJitterKernel`], which in turn costs one descriptor per thread.
+#[derive(Debug)]
+pub struct SharedRandJitterKernel {
+    rng: Mutex<RandJitterKernel>,
+}
+
+impl SharedRandJitterKernel {
+    /// constructs new shared RNG instance
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`].
+    pub fn new() -> Result<Self, std::io::Error> {
+        Ok(Self::from(RandJitterKernel::new()?))
+    }
+
+    /// returns a random `u32`
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u32`].
+    pub fn try_next_u32(&self) -> Result<u32, std::io::Error> {
+        self.lock().try_next_u32()
+    }
+
+    /// returns a random `u64`
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_next_u64`].
+    pub fn try_next_u64(&self) -> Result<u64, std::io::Error> {
+        self.lock().try_next_u64()
+    }
+
+    /// fills `dst` with random bytes
+    ///
+    /// # Errors
+    /// Same as [`TryRngCore::try_fill_bytes`].
+    pub fn try_fill_bytes(&self, dst: &mut [u8]) -> Result<(), std::io::Error> {
+        self.lock().try_fill_bytes(dst)
+    }
+
+    /// returns the wrapped instance
+    #[must_use]
+    pub fn into_inner(self) -> RandJitterKernel {
+        self.rng
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RandJitterKernel> {
+        // a panic while holding the lock leaves no broken invariant behind
+        self.rng.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<RandJitterKernel> for SharedRandJitterKernel {
+    fn from(rng: RandJitterKernel) -> Self {
+        SharedRandJitterKernel {
+            rng: Mutex::new(rng),
+        }
+    }
+}
+
+impl TryRngCore for &SharedRandJitterKernel {
+    type Error = std::io::Error;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        SharedRandJitterKernel::try_next_u32(self)
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        SharedRandJitterKernel::try_next_u64(self)
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        SharedRandJitterKernel::try_fill_bytes(self, dst)
+    }
+}
+
+impl TryRngCore for SharedRandJitterKernel {
+    type Error = std::io::Error;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        SharedRandJitterKernel::try_next_u32(self)
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        SharedRandJitterKernel::try_next_u64(self)
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        SharedRandJitterKernel::try_fill_bytes(self, dst)
+    }
+}
+
+impl TryCryptoRng for &SharedRandJitterKernel {}
+
+impl TryCryptoRng for SharedRandJitterKernel {}
+
+#[cfg(test)]
+mod tests {
+    use crate::SharedRandJitterKernel;
+    use crate::test_support::pipe_rng;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_shared_multi_threading() {
+        let rng = Arc::new(SharedRandJitterKernel::new().unwrap());
+        let mut threads = vec![];
+
+        for _ in 0..6 {
+            let rng = Arc::clone(&rng);
+            threads.push(std::thread::spawn(move || {
+                for _ in 0..256 {
+                    rng.try_next_u64().unwrap();
+                }
+            }));
+        }
+
+        for t in threads {
+            t.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_shared_from_fd() {
+        use rand_core::TryRngCore;
+
+        let (rng, _write_fd) = pipe_rng(&[0x42; 16]);
+        let rng = SharedRandJitterKernel::from(rng);
+        let mut by_ref = &rng;
+        assert_eq!(TryRngCore::try_next_u32(&mut by_ref).unwrap(), 0x4242_4242);
+        assert_eq!(rng.try_next_u32().unwrap(), 0x4242_4242);
+    }
+}
@@ -0,0 +1,230 @@
+//! Entropy cache filled by one background thread and drained by many threads
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crossbeam_queue::ArrayQueue;
+use rand_core::TryRngCore;
+
+use crate::{MAX_RETURN_CHUNK_SIZE, RandJitterKernel, wipe};
+
+/// number of bytes per entry of the cache, the unused rest of an entry is discarded
+const CACHE_ENTRY_SIZE: usize = 32;
+
+/// number of entries split off one read of the background thread
+const ENTRIES_PER_READ: usize = MAX_RETURN_CHUNK_SIZE / CACHE_ENTRY_SIZE;
+
+/// number of entries the cache holds by default, 2 KiB
+pub const DEFAULT_CACHE_ENTRIES: usize = 64;
+
+/// longest time the background thread sleeps on a full cache before checking it again, as
+/// readers wake it without taking the lock
+const FILLER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// one entry of entropy, wiped when dropped
+struct Entry([u8; CACHE_ENTRY_SIZE]);
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+/// state shared between the readers and the background thread
+struct Inner {
+    queue: ArrayQueue<Entry>,
+    /// error that stopped the background thread, guarded for the condition variables
+    error: Mutex<Option<std::io::Error>>,
+    /// signalled by the background thread after pushing entries or stopping
+    refilled: Condvar,
+    /// signalled by readers after taking entries
+    drained: Condvar,
+    shutdown: AtomicBool,
+}
+
+impl Inner {
+    fn lock_error(&self) -> MutexGuard<'_, Option<std::io::Error>> {
+        // the guarded state stays consistent even if a thread panicked while holding it
+        self.error.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// cache of entropy shared behind an [`Arc`], kept full by a background thread reading from
+/// one [`RandJitterKernel`]
+///
+/// Readers take entries of 32 bytes from a lock-free queue and only block while it is empty,
+/// so kernel reads are amortized across all threads. A request that is not a multiple of 32
+/// bytes discards the rest of its last entry. The thread stops after the first read error,
+/// which is then returned by every read once the cache is drained, and when the cache is
+/// dropped. Dropping waits for a read in progress.
+pub struct SharedEntropyCache {
+    inner: Arc<Inner>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SharedEntropyCache {
+    /// constructs new RNG instance and starts filling a cache of [`DEFAULT_CACHE_ENTRIES`]
+    /// entries
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::new`], or the error of spawning the thread.
+    pub fn new() -> Result<Arc<Self>, std::io::Error> {
+        Self::from_rng(RandJitterKernel::new()?, DEFAULT_CACHE_ENTRIES)
+    }
+
+    /// moves `rng` into a background thread keeping up to `entries` entries of 32 bytes ready,
+    /// at least the 4 entries of one read
+    ///
+    /// # Errors
+    /// Returns the error of spawning the thread.
+    pub fn from_rng(rng: RandJitterKernel, entries: usize) -> Result<Arc<Self>, std::io::Error> {
+        let inner = Arc::new(Inner {
+            queue: ArrayQueue::new(entries.max(ENTRIES_PER_READ)),
+            error: Mutex::new(None),
+            refilled: Condvar::new(),
+            drained: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+        });
+        let filler = Arc::clone(&inner);
+        let thread = std::thread::Builder::new()
+            .name("rand_jitter_cache".to_string())
+            .spawn(move || fill_cache(rng, &filler))?;
+
+        Ok(Arc::new(SharedEntropyCache {
+            inner,
+            thread: Some(thread),
+        }))
+    }
+
+    /// fills `dst` with random bytes from the cache, blocking while it is empty
+    ///
+    /// # Errors
+    /// Returns the error that stopped the background thread once the cache is drained. On
+    /// error, `dst` may be partially filled.
+    pub fn fill(&self, dst: &mut [u8]) -> Result<(), std::io::Error> {
+        for piece in dst.chunks_mut(CACHE_ENTRY_SIZE) {
+            let entry = self.pop()?;
+            piece.copy_from_slice(&entry.0[..piece.len()]);
+        }
+        Ok(())
+    }
+
+    fn pop(&self) -> Result<Entry, std::io::Error> {
+        loop {
+            if let Some(entry) = self.inner.queue.pop() {
+                self.inner.drained.notify_one();
+                return Ok(entry);
+            }
+
+            let mut error = self.inner.lock_error();
+            // the background thread pushes before taking the lock to notify, so checking again
+            // under the lock cannot miss its wakeup
+            while self.inner.queue.is_empty() {
+                if let Some(err) = error.as_ref() {
+                    return Err(std::io::Error::new(
+                        err.kind(),
+                        format!("cache thread of jitterentropy_rng stopped: {err}"),
+                    ));
+                }
+                error = self
+                    .inner
+                    .refilled
+                    .wait(error)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+        }
+    }
+}
+
+fn fill_cache(mut rng: RandJitterKernel, inner: &Inner) {
+    let mut block = [0u8; MAX_RETURN_CHUNK_SIZE];
+    while !inner.shutdown.load(Ordering::Acquire) {
+        if inner.queue.capacity() - inner.queue.len() < ENTRIES_PER_READ {
+            let error = inner.lock_error();
+            // readers notify without the lock, so a missed wakeup only delays the next check
+            let _ = inner
+                .drained
+                .wait_timeout(error, FILLER_POLL_INTERVAL)
+                .unwrap_or_else(PoisonError::into_inner);
+            continue;
+        }
+
+        if let Err(err) = rng.try_fill_bytes(&mut block) {
+            *inner.lock_error() = Some(err);
+            inner.refilled.notify_all();
+            return;
+        }
+        for piece in block.chunks_exact(CACHE_ENTRY_SIZE) {
+            let mut entry = Entry([0u8; CACHE_ENTRY_SIZE]);
+            entry.0.copy_from_slice(piece);
+            // only this thread pushes and there was room for a whole read
+            let _ = inner.queue.push(entry);
+        }
+        wipe(&mut block);
+
+        drop(inner.lock_error());
+        inner.refilled.notify_all();
+    }
+}
+
+impl std::fmt::Debug for SharedEntropyCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedEntropyCache")
+            .field("cached", &(self.inner.queue.len() * CACHE_ENTRY_SIZE))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for SharedEntropyCache {
+    fn drop(&mut self) {
+        self.inner.shutdown.store(true, Ordering::Release);
+        self.inner.drained.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SharedEntropyCache;
+    use crate::test_support::pipe_rng;
+
+    #[test]
+    fn test_shared_cache() {
+        let cache = SharedEntropyCache::new().unwrap();
+        let mut buffer = [0u8; 1000];
+        cache.fill(&mut buffer).unwrap();
+    }
+
+    #[test]
+    fn test_shared_cache_threads() {
+        use std::sync::Arc;
+
+        let (rng, write_fd) = pipe_rng(&[0x42; 8192]);
+        drop(write_fd);
+        let cache = SharedEntropyCache::from_rng(rng, 8).unwrap();
+        // 4 threads draining 2 KiB each take all 8 KiB in the pipe
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                std::thread::spawn(move || {
+                    let mut buffer = [0u8; 64];
+                    for _ in 0..32 {
+                        cache.fill(&mut buffer).unwrap();
+                        assert_eq!(buffer, [0x42; 64]);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // the pipe hit end of file, the thread reports it to every reader
+        assert!(cache.fill(&mut [0u8; 8]).is_err());
+        assert!(cache.fill(&mut [0u8; 8]).is_err());
+    }
+}
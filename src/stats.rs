@@ -0,0 +1,80 @@
+//! Latency statistics of the reads issued by an rng instance
+
+use std::time::Duration;
+
+/// minimum, maximum and average duration of the `read()` calls of an instance
+///
+/// Only successful reads are recorded, each including its retries after `EINTR`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    count: u64,
+    min: Duration,
+    max: Duration,
+    total: Duration,
+}
+
+impl LatencyStats {
+    /// number of recorded reads
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// shortest recorded read, zero if nothing was recorded yet
+    #[must_use]
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    /// longest recorded read, zero if nothing was recorded yet
+    #[must_use]
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// average duration of the recorded reads, zero if nothing was recorded yet
+    #[must_use]
+    pub fn avg(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        let avg = self.total.as_nanos() / u128::from(self.count);
+        Duration::from_nanos(u64::try_from(avg).unwrap_or(u64::MAX))
+    }
+
+    pub(crate) fn record(&mut self, latency: Duration) {
+        if self.count == 0 || latency < self.min {
+            self.min = latency;
+        }
+        self.max = self.max.max(latency);
+        self.total = self.total.saturating_add(latency);
+        self.count = self.count.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LatencyStats;
+    use std::time::Duration;
+
+    #[test]
+    fn test_empty() {
+        let stats = LatencyStats::default();
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.min(), Duration::ZERO);
+        assert_eq!(stats.max(), Duration::ZERO);
+        assert_eq!(stats.avg(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_record() {
+        let mut stats = LatencyStats::default();
+        for micros in [30, 10, 20] {
+            stats.record(Duration::from_micros(micros));
+        }
+        assert_eq!(stats.count(), 3);
+        assert_eq!(stats.min(), Duration::from_micros(10));
+        assert_eq!(stats.max(), Duration::from_micros(30));
+        assert_eq!(stats.avg(), Duration::from_micros(20));
+    }
+}
@@ -0,0 +1,139 @@
+//! Thin wrappers around the `libc` calls of the rng descriptor lifecycle, reporting failures
+//! as `std::io::Error`
+
+/// closes `fd`, errors are ignored as the descriptor is released either way
+pub(crate) fn close(fd: libc::c_int) {
+    let _ = close_checked(fd);
+}
+
+/// socket address of an `AF_ALG` socket
+#[cfg(target_os = "linux")]
+pub(crate) use libc::sockaddr_alg as SockaddrAlg;
+
+/// creates a socket and returns its descriptor
+#[cfg(target_os = "linux")]
+pub(crate) fn socket(
+    domain: libc::c_int,
+    ty: libc::c_int,
+    protocol: libc::c_int,
+) -> std::io::Result<libc::c_int> {
+    let fd = unsafe { libc::socket(domain, ty, protocol) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(fd)
+}
+
+/// binds `fd` to the `AF_ALG` address `addr`
+#[cfg(target_os = "linux")]
+pub(crate) fn bind(fd: libc::c_int, addr: &SockaddrAlg) -> std::io::Result<()> {
+    let len = u32::try_from(std::mem::size_of_val(addr))
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    let ret = unsafe { libc::bind(fd, std::ptr::from_ref(addr).cast::<libc::sockaddr>(), len) };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// accepts a connection on `fd` without querying the peer address
+#[cfg(target_os = "linux")]
+pub(crate) fn accept4(fd: libc::c_int, flags: libc::c_int) -> std::io::Result<libc::c_int> {
+    let ret = unsafe { libc::accept4(fd, std::ptr::null_mut(), std::ptr::null_mut(), flags) };
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(ret)
+}
+
+/// issues a single `read()` of up to `len` bytes on `fd` into `buf`
+///
+/// # Safety
+/// `buf` must be valid for writes of `len` bytes, which may be uninitialized.
+pub(crate) unsafe fn read(fd: libc::c_int, buf: *mut u8, len: usize) -> std::io::Result<usize> {
+    let size = unsafe { libc::read(fd, buf.cast::<libc::c_void>(), len) };
+    // -1 is the only negative result, any count is at most len and thus fits into usize
+    if size < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(size.unsigned_abs())
+}
+
+/// issues a single `readv()` on `fd` into the slices described by `iovecs`
+///
+/// # Safety
+/// Every iovec must be valid for writes of its length.
+pub(crate) unsafe fn readv(fd: libc::c_int, iovecs: &[libc::iovec]) -> std::io::Result<usize> {
+    let count = libc::c_int::try_from(iovecs.len())
+        .map_err(|_| std::io::Error::from_raw_os_error(libc::EINVAL))?;
+    let size = unsafe { libc::readv(fd, iovecs.as_ptr(), count) };
+    // like read(), -1 is the only negative result
+    if size < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(size.unsigned_abs())
+}
+
+/// closes `fd`, the descriptor is released even if an error is returned
+pub(crate) fn close_checked(fd: libc::c_int) -> std::io::Result<()> {
+    if unsafe { libc::close(fd) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::pipe_with;
+
+    #[test]
+    fn test_sys_read_close() {
+        let data = [0x42u8; 16];
+        let (read_fd, write_fd) = pipe_with(&data);
+
+        let mut buf = [0u8; 32];
+        let size = unsafe { super::read(read_fd, buf.as_mut_ptr(), buf.len()) }.unwrap();
+        assert_eq!(&buf[..size], &data);
+
+        super::close(read_fd);
+        super::close(write_fd);
+        let err = unsafe { super::read(-1, buf.as_mut_ptr(), buf.len()) }.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    fn test_sys_readv() {
+        let data: Vec<u8> = (0..12).collect();
+        let (read_fd, write_fd) = pipe_with(&data);
+
+        let (mut first, mut second) = ([0u8; 4], [0u8; 8]);
+        let iovecs = [
+            libc::iovec {
+                iov_base: first.as_mut_ptr().cast(),
+                iov_len: first.len(),
+            },
+            libc::iovec {
+                iov_base: second.as_mut_ptr().cast(),
+                iov_len: second.len(),
+            },
+        ];
+        assert_eq!(unsafe { super::readv(read_fd, &iovecs) }.unwrap(), 12);
+        assert_eq!(first, [0, 1, 2, 3]);
+        assert_eq!(second, [4, 5, 6, 7, 8, 9, 10, 11]);
+
+        super::close(read_fd);
+        super::close(write_fd);
+        let err = unsafe { super::readv(-1, &iovecs) }.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_sys_socket_errors() {
+        let err = super::socket(-1, libc::SOCK_SEQPACKET, 0).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EAFNOSUPPORT));
+
+        let err = super::accept4(-1, libc::SOCK_CLOEXEC).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+}
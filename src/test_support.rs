@@ -0,0 +1,55 @@
+//! Pipe and socket fixtures shared by the unit tests of all modules
+
+use std::os::fd::{FromRawFd, OwnedFd};
+
+use crate::RandJitterKernel;
+
+/// creates a pipe and returns its read and write end
+pub(crate) fn pipe() -> (libc::c_int, libc::c_int) {
+    let mut fds = [0; 2];
+    assert_eq!(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) }, 0);
+    (fds[0], fds[1])
+}
+
+/// creates a pipe holding `data` and returns its read and write end
+pub(crate) fn pipe_with(data: &[u8]) -> (libc::c_int, libc::c_int) {
+    let (read_fd, write_fd) = pipe();
+    let written = unsafe { libc::write(write_fd, data.as_ptr().cast(), data.len()) };
+    assert_eq!(usize::try_from(written).ok(), Some(data.len()));
+    (read_fd, write_fd)
+}
+
+/// returns an rng reading `data` from a pipe, together with the write end
+///
+/// Dropping the write end lets reads hit end of file once `data` is drained.
+pub(crate) fn pipe_rng(data: &[u8]) -> (RandJitterKernel, OwnedFd) {
+    let (read_fd, write_fd) = pipe_with(data);
+    unsafe {
+        (
+            RandJitterKernel::from_raw_fd(read_fd),
+            OwnedFd::from_raw_fd(write_fd),
+        )
+    }
+}
+
+/// creates a connected pair of `AF_UNIX` seqpacket sockets, which keep packet boundaries like an
+/// `AF_ALG` rng socket returns one chunk per read
+pub(crate) fn seqpacket_pair() -> (libc::c_int, libc::c_int) {
+    let mut fds = [0; 2];
+    let ret = unsafe {
+        libc::socketpair(
+            libc::AF_UNIX,
+            libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC,
+            0,
+            fds.as_mut_ptr(),
+        )
+    };
+    assert_eq!(ret, 0);
+    (fds[0], fds[1])
+}
+
+/// sends `data` as a single packet on `fd`
+pub(crate) fn send_packet(fd: libc::c_int, data: &[u8]) {
+    let sent = unsafe { libc::write(fd, data.as_ptr().cast(), data.len()) };
+    assert_eq!(usize::try_from(sent).ok(), Some(data.len()));
+}
@@ -0,0 +1,104 @@
+//! Lazily opened rng instance per thread, reused for every call on that thread
+
+use std::cell::RefCell;
+
+use rand_core::{TryCryptoRng, TryRngCore};
+
+use crate::RandJitterKernel;
+
+thread_local! {
+    // dropped by the thread-local destructor at thread exit, which closes the descriptor
+    static LOCAL: RefCell<Option<RandJitterKernel>> = const { RefCell::new(None) };
+}
+
+/// returns a handle to the rng instance of the calling thread
+///
+/// The instance is opened on first use per thread and closed when the thread exits. Threads do
+/// not share descriptors, so no lock is taken.
+#[must_use]
+pub fn thread_local_rng() -> ThreadLocalRng {
+    ThreadLocalRng
+}
+
+/// handle to the rng instance of the thread using it, see [`thread_local_rng`]
+///
+/// If opening the instance fails, the error is returned and the next call tries again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ThreadLocalRng;
+
+impl ThreadLocalRng {
+    fn with<T>(
+        f: impl FnOnce(&mut RandJitterKernel) -> Result<T, std::io::Error>,
+    ) -> Result<T, std::io::Error> {
+        LOCAL
+            .try_with(|local| {
+                let mut local = local.borrow_mut();
+                let rng = match &mut *local {
+                    Some(rng) => rng,
+                    None => local.insert(RandJitterKernel::new()?),
+                };
+                f(rng)
+            })
+            // only fails while the thread-local destructors of this thread run
+            .map_err(std::io::Error::other)?
+    }
+}
+
+impl TryRngCore for ThreadLocalRng {
+    type Error = std::io::Error;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        Self::with(TryRngCore::try_next_u32)
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        Self::with(TryRngCore::try_next_u64)
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        Self::with(|rng| rng.try_fill_bytes(dst))
+    }
+}
+
+impl TryCryptoRng for ThreadLocalRng {}
+
+#[cfg(test)]
+mod tests {
+    use super::{ThreadLocalRng, thread_local_rng};
+    use crate::test_support::pipe;
+    use rand_core::TryRngCore;
+
+    #[test]
+    fn test_thread_local_reused() {
+        let mut rng = thread_local_rng();
+        rng.try_next_u64().unwrap();
+        let first = ThreadLocalRng::with(|rng| Ok(rng.rng_fd)).unwrap();
+        rng.try_next_u64().unwrap();
+        let second = ThreadLocalRng::with(|rng| Ok(rng.rng_fd)).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_thread_local_closed_at_exit() {
+        use std::os::fd::FromRawFd;
+
+        let (read_fd, write_fd) = pipe();
+        std::thread::spawn(move || {
+            let rng = unsafe { crate::RandJitterKernel::from_raw_fd(read_fd) };
+            super::LOCAL.with(|local| *local.borrow_mut() = Some(rng));
+            let fd = ThreadLocalRng::with(|rng| Ok(rng.rng_fd)).unwrap();
+            assert_eq!(fd, read_fd);
+        })
+        .join()
+        .unwrap();
+
+        // the read end was closed at thread exit, so writing fails with EPIPE
+        let ret = unsafe { libc::write(write_fd, [0u8].as_ptr().cast(), 1) };
+        assert_eq!(ret, -1);
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error(),
+            Some(libc::EPIPE)
+        );
+        unsafe { libc::close(write_fd) };
+    }
+}
@@ -0,0 +1,103 @@
+//! Guard applying a read timeout for a limited scope
+
+use std::ops::{Deref, DerefMut};
+use std::time::Duration;
+
+use crate::RandJitterKernel;
+
+/// restores the previous read timeout of an rng instance when dropped
+///
+/// Returned by [`RandJitterKernel::with_timeout`], the instance is used through the guard.
+#[derive(Debug)]
+pub struct TimeoutGuard<'a> {
+    rng: &'a mut RandJitterKernel,
+    previous: Option<Duration>,
+}
+
+impl RandJitterKernel {
+    /// sets the read timeout to `timeout` until the returned guard is dropped, which restores
+    /// the previous timeout, also if a `?` returns early
+    ///
+    /// # Errors
+    /// Same as [`RandJitterKernel::set_read_timeout`].
+    pub fn with_timeout(&mut self, timeout: Duration) -> Result<TimeoutGuard<'_>, std::io::Error> {
+        let previous = self.options.read_timeout;
+        self.set_read_timeout(Some(timeout))?;
+        Ok(TimeoutGuard {
+            rng: self,
+            previous,
+        })
+    }
+}
+
+impl Deref for TimeoutGuard<'_> {
+    type Target = RandJitterKernel;
+
+    fn deref(&self) -> &Self::Target {
+        self.rng
+    }
+}
+
+impl DerefMut for TimeoutGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.rng
+    }
+}
+
+impl Drop for TimeoutGuard<'_> {
+    fn drop(&mut self) {
+        // restoring only fails if the descriptor itself broke, which the next read reports
+        let _ = self.rng.set_read_timeout(self.previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RandJitterKernel;
+    use crate::test_support::seqpacket_pair;
+    use rand_core::TryRngCore;
+    use std::time::Duration;
+
+    fn read_timeout(fd: libc::c_int) -> libc::timeval {
+        let mut timeval = libc::timeval {
+            tv_sec: 0,
+            tv_usec: 0,
+        };
+        let mut len = libc::socklen_t::try_from(size_of::<libc::timeval>()).unwrap();
+        let ret = unsafe {
+            libc::getsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                std::ptr::addr_of_mut!(timeval).cast(),
+                &raw mut len,
+            )
+        };
+        assert_eq!(ret, 0);
+        timeval
+    }
+
+    fn fill_with_timeout(rng: &mut RandJitterKernel) -> Result<(), std::io::Error> {
+        let mut guard = rng.with_timeout(Duration::from_millis(20))?;
+        guard.try_fill_bytes(&mut [0u8; 16])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeout_restored_on_error() {
+        use std::os::fd::FromRawFd;
+
+        let (rng_fd, peer_fd) = seqpacket_pair();
+        let mut rng = unsafe { RandJitterKernel::from_raw_fd(rng_fd) };
+
+        // nothing is sent, so the read times out and the fill returns early
+        let err = fill_with_timeout(&mut rng).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+        assert_eq!(rng.options.read_timeout, None);
+        let timeval = read_timeout(rng_fd);
+        assert_eq!((timeval.tv_sec, timeval.tv_usec), (0, 0));
+
+        unsafe { libc::close(peer_fd) };
+    }
+}
@@ -0,0 +1,182 @@
+//! Batched chunk reads via `io_uring` for large fills
+
+use io_uring::{IoUring, opcode, types};
+
+/// number of chunk reads submitted at once
+pub(crate) const URING_BATCH: usize = 64;
+
+/// ring of an rng instance, set up on its first large fill and reused afterwards
+#[derive(Default)]
+pub(crate) struct LazyRing {
+    state: RingState,
+}
+
+#[derive(Default)]
+enum RingState {
+    #[default]
+    Untried,
+    Ready(Box<Ring>),
+    /// `io_uring` is disabled, e.g. by seccomp or `kernel.io_uring_disabled`, or the ring broke
+    Unavailable,
+}
+
+struct Ring {
+    ring: IoUring,
+    /// the kernel reads into this buffer first, so a short chunk read never leaves a gap in `dst`
+    staging: Vec<u8>,
+}
+
+impl LazyRing {
+    /// fills `dst` with up to [`URING_BATCH`] chunk reads per submission and returns the size of
+    /// the filled prefix of `dst`, 0 if `io_uring` is unavailable
+    ///
+    /// Only whole chunks are read, filling stops at the first short or failed chunk read. The
+    /// caller fills the rest with `read()`, which also reports the error of a failed chunk and
+    /// reopens the descriptor if configured to.
+    pub(crate) fn fill(&mut self, fd: libc::c_int, dst: &mut [u8], chunk_size: usize) -> usize {
+        if matches!(self.state, RingState::Untried) {
+            self.state = u32::try_from(URING_BATCH)
+                .ok()
+                .and_then(|entries| IoUring::new(entries).ok())
+                .map_or(RingState::Unavailable, |ring| {
+                    RingState::Ready(Box::new(Ring {
+                        ring,
+                        staging: Vec::new(),
+                    }))
+                });
+        }
+        let RingState::Ready(ring) = &mut self.state else {
+            return 0;
+        };
+
+        match ring.fill(fd, dst, chunk_size) {
+            Ok(filled) => filled,
+            Err(filled) => {
+                // reads may still be in flight, so the staging buffer is leaked with the ring
+                // instead of freed
+                if let RingState::Ready(ring) = std::mem::take(&mut self.state) {
+                    std::mem::forget(ring);
+                }
+                self.state = RingState::Unavailable;
+                filled
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn ring_fd(&self) -> Option<libc::c_int> {
+        use std::os::fd::AsRawFd;
+
+        match &self.state {
+            RingState::Ready(ring) => Some(ring.ring.as_raw_fd()),
+            _ => None,
+        }
+    }
+}
+
+impl Ring {
+    /// returns the size of the filled prefix, as error if waiting for the completions failed
+    fn fill(&mut self, fd: libc::c_int, dst: &mut [u8], chunk_size: usize) -> Result<usize, usize> {
+        let Ok(chunk_len) = u32::try_from(chunk_size) else {
+            return Ok(0);
+        };
+        if chunk_size == 0 {
+            return Ok(0);
+        }
+        // the chunk size changes if the instance reopened its descriptor
+        self.staging.resize(URING_BATCH * chunk_size, 0);
+
+        let mut filled = 0;
+        while dst.len() - filled >= chunk_size {
+            let chunks = ((dst.len() - filled) / chunk_size).min(URING_BATCH);
+            for (i, buf) in self
+                .staging
+                .chunks_exact_mut(chunk_size)
+                .take(chunks)
+                .enumerate()
+            {
+                // i < URING_BATCH always fits into the user data
+                let entry = opcode::Read::new(types::Fd(fd), buf.as_mut_ptr(), chunk_len)
+                    .build()
+                    .user_data(i as u64);
+                // the queue holds URING_BATCH entries and was drained by the previous batch,
+                // the staging buffer stays alive until all reads completed or is leaked
+                if unsafe { self.ring.submission().push(&entry) }.is_err() {
+                    return Err(filled);
+                }
+            }
+
+            let mut results = [-1i32; URING_BATCH];
+            let mut done = 0;
+            while done < chunks {
+                match self.ring.submit_and_wait(chunks - done) {
+                    Ok(_) => {}
+                    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(_) => return Err(filled),
+                }
+                for cqe in self.ring.completion() {
+                    if let Some(result) = usize::try_from(cqe.user_data())
+                        .ok()
+                        .and_then(|i| results.get_mut(i))
+                    {
+                        *result = cqe.result();
+                    }
+                    done += 1;
+                }
+            }
+
+            let mut complete = true;
+            for (result, buf) in results
+                .iter()
+                .take(chunks)
+                .zip(self.staging.chunks_exact(chunk_size))
+            {
+                if usize::try_from(*result).ok() != Some(chunk_size) {
+                    complete = false;
+                    break;
+                }
+                dst[filled..filled + chunk_size].copy_from_slice(buf);
+                filled += chunk_size;
+            }
+            if !complete {
+                break;
+            }
+        }
+
+        crate::wipe(&mut self.staging);
+        Ok(filled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LazyRing;
+    use crate::test_support::pipe_with;
+
+    #[test]
+    fn test_uring_fill_pipe() {
+        let (read_fd, write_fd) = pipe_with(&[0x42; 1000]);
+
+        let mut ring = LazyRing::default();
+        let mut buf = [0u8; 600];
+        let filled = ring.fill(read_fd, &mut buf, 128);
+        // io_uring may be disabled, e.g. by seccomp or kernel.io_uring_disabled
+        if let Some(ring_fd) = ring.ring_fd() {
+            assert_eq!(filled, 512);
+            assert_eq!(buf[..512], [0x42; 512]);
+            assert_eq!(buf[512..], [0; 88]);
+
+            // the second fill reuses the ring set up by the first
+            let mut buf = [0u8; 256];
+            assert_eq!(ring.fill(read_fd, &mut buf, 128), 256);
+            assert_eq!(ring.ring_fd(), Some(ring_fd));
+        } else {
+            assert_eq!(filled, 0);
+        }
+
+        unsafe {
+            libc::close(read_fd);
+            libc::close(write_fd);
+        }
+    }
+}
@@ -1,8 +1,8 @@
 //! Integration Test Crate
 
-use rand::{Rng, RngCore, SeedableRng, TryRngCore};
+use rand::{Rng, RngCore, SeedableRng, TryCryptoRng, TryRngCore};
 use rand_chacha::ChaCha20Rng;
-use rand_jitter_kernel::RandJitterKernel;
+use rand_jitter_kernel::{JitterRng, RandJitterKernel, SharedRandJitterKernel};
 use rand_xoshiro::{SplitMix64, Xoshiro256PlusPlus};
 
 #[test]
@@ -28,3 +28,13 @@ fn test_rngs() {
         let _ = splitmix_rng.next_u64();
     }
 }
+
+#[test]
+fn test_crypto_rng_marker() {
+    fn assert_crypto_rng<R: TryCryptoRng>() {}
+
+    assert_crypto_rng::<RandJitterKernel>();
+    assert_crypto_rng::<SharedRandJitterKernel>();
+    assert_crypto_rng::<&SharedRandJitterKernel>();
+    assert_crypto_rng::<JitterRng>();
+}